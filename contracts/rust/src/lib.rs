@@ -2,17 +2,27 @@
 extern crate alloc;
 extern crate fluentbase_sdk;
 
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm,
+    Key,
+    Nonce,
+};
 use alloc::string::String;
 use alloc::vec::Vec;
+use chacha20poly1305::ChaCha20Poly1305;
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
 use fluentbase_sdk::{
     basic_entrypoint,
     derive::{function_id, router, Contract, solidity_storage},
     Address,
     Bytes,
+    B256,
     U256,
     SharedAPI,
     ContractContextReader,
     BlockContextReader,
+    keccak256,
 };
 
 // Define Note structure
@@ -21,48 +31,379 @@ struct Note {
     id: U256,
     owner: Address,
     encrypted_content: Bytes,
-    timestamp: U256,
+    created_at: U256,
+    updated_at: U256,
     title: String,
+    key_version: U256,
+    // AEAD the content was sealed with (CIPHER_*)
+    cipher_mode: u8,
 }
 
 // Define storage for user data
 solidity_storage! {
+    // Admin of the contract, initially the deployer
+    Address ContractOwner;
+    
+    // Emergency stop for every state-changing note and key operation
+    bool Paused;
+    
+    // Strict mode: refuse to encrypt under the address-derived fallback key
+    bool RequireEncryptionKey;
+    
+    // Next global note id; ids are unique across all users and never reused
+    U256 GlobalNoteCounter;
+    
+    // Contract-wide usage: notes outside the recycle bin, and registered addresses
+    U256 TotalNotes;
+    U256 TotalUsers;
+    
+    // Addresses that registered, explicitly or on first use
+    mapping(Address => bool) RegisteredUsers;
+    
     // Storage for encryption keys
     mapping(Address => Bytes) UserEncryptionKeys;
     
-    // Storage for notes count
+    // Per-user counter mixed into every AES-GCM nonce
+    mapping(Address => U256) UserNonceCounter;
+    
+    // Key being rotated in and how many notes have been re-encrypted under it so far
+    mapping(Address => Bytes) PendingEncryptionKeys;
+    mapping(Address => U256) KeyRotationCursor;
+    
+    // Key history: the current version number per user and every key it replaced
+    mapping(Address => U256) UserKeyVersion;
+    mapping(Address => mapping(U256 => Bytes)) UserKeyVersions;
+    
+    // Per-user list of note ids: UserNotesCount is its length, UserNoteIds the entries,
+    // and NoteIndex the position of each note in its owner's list
     mapping(Address => U256) UserNotesCount;
+    mapping(Address => mapping(U256 => U256)) UserNoteIds;
+    mapping(U256 => U256) NoteIndex;
+    
+    // Per-user override of MAX_NOTES_PER_USER; zero means unset
+    mapping(Address => U256) UserNoteLimit;
+    
+    // Running total of ciphertext bytes stored per user
+    mapping(Address => U256) UserStorageBytes;
+    
+    // Notes a user has ever created; purges and transfers never lower it
+    mapping(Address => U256) UserLifetimeNotesCreated;
+    
+    // Next nonce a relayed createNoteSigned must carry (separate from the encryption nonce counter)
+    mapping(Address => U256) UserSignedNoteNonce;
+    
+    // Relayers an owner lets call createNoteFor in their name: owner => delegate => allowed
+    mapping(Address => mapping(Address => bool)) CreateDelegates;
+    
+    // Opt-out of the control-character check on titles the user writes
+    mapping(Address => bool) UserAllowsControlChars;
+    
+    // AEAD new ciphertext is sealed with (CIPHER_*); each note records the one it used
+    mapping(Address => U256) UserCipherMode;
     
     // Storage map for notes - we'll use multiple mappings for each field
-    // Using the pattern noteId => value for each field
+    // Using the pattern noteId => value for each field, keyed by the global note id
     mapping(U256 => U256) NoteId;
     mapping(U256 => Address) NoteOwner;
-    mapping(U256 => Bytes) NoteContent;
-    mapping(U256 => U256) NoteTimestamp;
+    // Ciphertext split into NOTE_CHUNK_SIZE pieces; read and written through load/store_note_content
+    mapping(U256 => U256) NoteChunkCount;
+    mapping(U256 => mapping(U256 => Bytes)) NoteContentChunk;
+    mapping(U256 => U256) NoteCreatedAt;
+    mapping(U256 => U256) NoteUpdatedAt;
+    // Block the note was created in; harder for block producers to skew than the timestamp
+    mapping(U256 => U256) NoteCreatedBlock;
     mapping(U256 => String) NoteTitle;
+    
+    // Version of the owner's key each note's content key is wrapped under
+    mapping(U256 => U256) NotePerKeyId;
+    
+    // Per-owner title index: keccak256(title) => note id + 1 (zero means no entry).
+    // Last writer wins when several notes share a title.
+    mapping(Address => mapping(fluentbase_sdk::B256 => U256)) TitleToNoteId;
+    
+    // Tags per note, stored as a count plus an indexed list
+    mapping(U256 => U256) NoteTagCount;
+    mapping(U256 => mapping(U256 => String)) NoteTag;
+    
+    // Directed links to other notes of the same owner, in the order they were added
+    mapping(U256 => U256) NoteLinkCount;
+    mapping(U256 => mapping(U256 => U256)) NoteLinkAt;
+    
+    // Soft-delete flag; flagged notes are hidden from reads and listings until restored
+    mapping(U256 => bool) NoteDeleted;
+    
+    // Addresses the owner granted access to, per note; they can read it
+    mapping(U256 => mapping(Address => bool)) NoteSharedWith;
+    // Grantees the owner also lets edit the note; cleared when their share goes
+    mapping(U256 => mapping(Address => bool)) NoteEditors;
+    // The same grantees as an indexed list, so they can be moved or cleared with the note
+    mapping(U256 => U256) NoteShareCount;
+    mapping(U256 => mapping(U256 => Address)) NoteShareAt;
+    // The note's own content key wrapped under each holder's key (see wrap_note_key); the owner
+    // holds one like every grantee
+    mapping(U256 => mapping(Address => Bytes)) NoteEnvelope;
+    // Reverse of the share lists: per grantee, the ids of notes shared with them (unordered),
+    // and each note's position in that list plus one so 0 means absent
+    mapping(Address => U256) SharedWithMeCount;
+    mapping(Address => mapping(U256 => U256)) SharedWithMeAt;
+    mapping(U256 => mapping(Address => U256)) SharedWithMeIndex;
+    
+    // Public notes can be read by anyone
+    mapping(U256 => bool) NotePublic;
+    
+    // Archived notes are kept (and counted) but left out of the default listings
+    mapping(U256 => bool) NoteArchived;
+    
+    // Folder id per note; 0 means uncategorized
+    mapping(U256 => U256) NoteCategory;
+    
+    // Pinned (favorite) notes, surfaced in paged listings
+    mapping(U256 => bool) NotePinned;
+    
+    // Manual display position per note; getNotesList sorts by it
+    mapping(U256 => U256) NoteSortOrder;
+    
+    // Previous ciphertexts of a note (oldest kept first) with the key version each was written under
+    mapping(U256 => U256) NoteVersionCount;
+    mapping(U256 => mapping(U256 => Bytes)) NoteVersionContent;
+    mapping(U256 => mapping(U256 => U256)) NoteVersionKeyId;
+    mapping(U256 => mapping(U256 => U256)) NoteVersionCipherMode;
+    
+    // keccak256 of the plaintext as last written, to detect corrupted ciphertext
+    mapping(U256 => B256) NoteContentHash;
+    
+    // Who last wrote a note's content: the owner or one of its editors
+    mapping(U256 => Address) NoteLastEditor;
+    
+    // AEAD the current content was sealed with (CIPHER_*)
+    mapping(U256 => U256) NoteCipherMode;
+    
+    // How the frontend should render the content (CONTENT_TYPE_*)
+    mapping(U256 => U256) NoteContentType;
+    
+    // Color label as packed 0xRRGGBB, 0 for none
+    mapping(U256 => U256) NoteColor;
+    
+    // Optional binary attachment, encrypted like the content (owner || nonce || ciphertext || tag).
+    // It can be written after the content, so it records its own key version and cipher
+    mapping(U256 => Bytes) NoteAttachment;
+    mapping(U256 => U256) NoteAttachmentKeyVersion;
+    mapping(U256 => U256) NoteAttachmentCipherMode;
+    
+    // Off-chain attachment reference (an IPFS CID or other content hash), stored as given
+    mapping(U256 => Bytes) NoteAttachmentCid;
+    
+    // Locked notes are read-only: no update, delete or purge until unlocked
+    mapping(U256 => bool) NoteLocked;
+    
+    // Block time from which a note reads as missing; 0 means it never expires
+    mapping(U256 => U256) NoteExpiresAt;
 }
 
 // Event signature constants - pre-computed keccak256 hashes
+// keccak256("NoteCreated(address,uint256,bytes32,string,uint256,uint256)")
 const EVENT_NOTE_CREATED: [u8; 32] = [
-    0xa5, 0x63, 0x76, 0x16, 0x0d, 0x28, 0xd2, 0xb9, 0x0a, 0x07, 0x46, 0xc4, 0x9c, 0xab, 0xa7, 0x32,
-    0xb4, 0x76, 0x70, 0xe9, 0xd5, 0x1b, 0xc3, 0x9e, 0x43, 0x1f, 0x4a, 0x6d, 0x86, 0x1a, 0x0f, 0x9d
+    0xe6, 0xae, 0x1c, 0x6c, 0x70, 0x7c, 0xfb, 0x0b, 0xf9, 0x46, 0xfb, 0x6b, 0x5a, 0x26, 0x68, 0x9f,
+    0x5c, 0x7a, 0x85, 0x76, 0x56, 0x2e, 0xe8, 0xf5, 0x91, 0x63, 0x71, 0xb0, 0xba, 0xbf, 0xc6, 0xff
 ];
 
+// keccak256("NoteUpdated(address,uint256,bytes32,string,uint256,uint256,string)")
 const EVENT_NOTE_UPDATED: [u8; 32] = [
-    0x9b, 0x87, 0x18, 0x32, 0x9e, 0xe8, 0xd9, 0x34, 0xe3, 0xcb, 0x45, 0xc9, 0x85, 0x18, 0xea, 0x81,
-    0xc6, 0xec, 0x5e, 0xa3, 0xb1, 0x1d, 0xd7, 0x7b, 0x32, 0x53, 0xe5, 0x9e, 0x67, 0xc0, 0x5c, 0x8a
+    0xb7, 0xfd, 0x12, 0xab, 0xe4, 0xb9, 0x7b, 0xd9, 0xe8, 0x95, 0xb2, 0xaa, 0xe6, 0x09, 0x78, 0xdc,
+    0xfc, 0x50, 0xc5, 0x2b, 0x9c, 0x1c, 0xdd, 0xe5, 0xd3, 0x89, 0x65, 0x6a, 0x54, 0x6f, 0xdb, 0x15
 ];
 
+// keccak256("NoteDeleted(address,uint256,uint256)")
 const EVENT_NOTE_DELETED: [u8; 32] = [
-    0x95, 0xa3, 0x23, 0xc3, 0x16, 0x9c, 0xe1, 0xb2, 0x12, 0xd9, 0x54, 0x54, 0xf3, 0x14, 0xa7, 0xef,
-    0x7d, 0xd4, 0x8d, 0xc5, 0x74, 0x8b, 0xe2, 0xc6, 0x6b, 0x0a, 0x52, 0xd1, 0x02, 0xf2, 0x80, 0xc4
+    0xd1, 0xc5, 0xc4, 0x2d, 0x01, 0xd7, 0x62, 0xdd, 0x53, 0xe9, 0x9e, 0x87, 0x8b, 0x8d, 0x36, 0xd6,
+    0x9e, 0x71, 0x09, 0x05, 0x09, 0x95, 0xef, 0x58, 0x68, 0xc0, 0x0e, 0x79, 0xd7, 0xe8, 0x3d, 0x63
 ];
 
+// keccak256("UserRegistered(address)")
 const EVENT_USER_REGISTERED: [u8; 32] = [
-    0x87, 0x7a, 0x15, 0x53, 0x68, 0xc1, 0xf0, 0xde, 0x44, 0xcf, 0xba, 0x7a, 0x7d, 0xa9, 0x05, 0xcb,
-    0xae, 0xeb, 0x31, 0x94, 0x3d, 0x83, 0x9b, 0x7c, 0x67, 0x10, 0x3a, 0xca, 0xa5, 0x30, 0x09, 0xf5
+    0x54, 0xdb, 0x7a, 0x5c, 0xb4, 0x73, 0x5e, 0x1a, 0xac, 0x1f, 0x53, 0xdb, 0x51, 0x2d, 0x33, 0x90,
+    0x39, 0x0b, 0xb6, 0x63, 0x7b, 0xd3, 0x0a, 0xd4, 0xbf, 0x9f, 0xc9, 0x86, 0x67, 0xd9, 0xb9, 0xb9
+];
+
+// keccak256("NoteShared(address,address,uint256)")
+const EVENT_NOTE_SHARED: [u8; 32] = [
+    0x51, 0xcf, 0xf3, 0x08, 0x0a, 0x05, 0x22, 0x7b, 0x4b, 0x30, 0xdc, 0x39, 0x61, 0x03, 0x03, 0xc6,
+    0xfc, 0xf1, 0x60, 0x96, 0x11, 0x77, 0x28, 0x53, 0x50, 0x25, 0x4f, 0x15, 0xed, 0xdd, 0x7e, 0xd7
+];
+
+// keccak256("NoteTransferred(address,address,uint256,uint256)")
+const EVENT_NOTE_TRANSFERRED: [u8; 32] = [
+    0x6d, 0x33, 0xdb, 0x85, 0xd2, 0xb9, 0x31, 0x51, 0x58, 0x07, 0x46, 0x35, 0x23, 0x84, 0x79, 0xb7,
+    0x23, 0x14, 0x11, 0xf3, 0x75, 0xe5, 0xd2, 0xd4, 0xea, 0xc1, 0x88, 0x36, 0xd2, 0x40, 0x8c, 0xd0
+];
+
+// keccak256("NoteVisibilityChanged(address,uint256,bool)")
+const EVENT_NOTE_VISIBILITY_CHANGED: [u8; 32] = [
+    0x44, 0x56, 0x35, 0x41, 0x2c, 0x66, 0x9b, 0x34, 0x12, 0x6c, 0x29, 0x0e, 0xa2, 0xfd, 0x1f, 0xea,
+    0x80, 0x29, 0x4d, 0x3d, 0x9b, 0xcf, 0xbc, 0xfd, 0x96, 0x2c, 0x62, 0xb2, 0xec, 0xe2, 0x6b, 0x35
+];
+
+// keccak256("NoteArchived(address,uint256,bool)")
+const EVENT_NOTE_ARCHIVED: [u8; 32] = [
+    0xd6, 0x37, 0x9e, 0xed, 0x34, 0x15, 0xbf, 0x20, 0xf8, 0xb9, 0x5e, 0xb5, 0x07, 0x6f, 0x58, 0x89,
+    0xea, 0xa2, 0x72, 0xa8, 0xb5, 0xd4, 0x4e, 0xb7, 0x97, 0x6e, 0x56, 0xce, 0xc7, 0xa8, 0x8c, 0xfc
+];
+
+// keccak256("KeyUpdated(address,bytes32)")
+const EVENT_KEY_UPDATED: [u8; 32] = [
+    0xdb, 0x4c, 0xc7, 0xe9, 0x53, 0x11, 0xb1, 0xd7, 0x15, 0x07, 0x4f, 0x6f, 0x31, 0xca, 0x46, 0xf6,
+    0x07, 0x8a, 0x05, 0x1c, 0xa5, 0x29, 0x8e, 0xa0, 0x63, 0x79, 0x2e, 0xca, 0xa5, 0x2c, 0x22, 0x6f
 ];
 
+// keccak256("NoteCategorized(address,uint256,uint256)")
+const EVENT_NOTE_CATEGORIZED: [u8; 32] = [
+    0x47, 0xe1, 0xa0, 0x36, 0xb1, 0x83, 0xfd, 0x47, 0x21, 0x5b, 0x17, 0x07, 0x10, 0xbd, 0x8c, 0x3f,
+    0x36, 0xf3, 0x78, 0x96, 0x77, 0x52, 0x2a, 0x63, 0x07, 0xd5, 0x36, 0x69, 0x58, 0x24, 0x98, 0x26
+];
+
+// keccak256("NoteAccessed(address,uint256)")
+const EVENT_NOTE_ACCESSED: [u8; 32] = [
+    0x29, 0xa3, 0x62, 0xa3, 0xb6, 0x8f, 0xfa, 0x13, 0x7c, 0x38, 0x1b, 0xae, 0xb0, 0x4d, 0x1c, 0xaa,
+    0xd9, 0x62, 0xed, 0x25, 0x78, 0x44, 0xb9, 0x14, 0xd1, 0x8a, 0x08, 0x08, 0x63, 0x24, 0x65, 0x87
+];
+
+// keccak256("AllNotesDeleted(address,uint256)")
+const EVENT_ALL_NOTES_DELETED: [u8; 32] = [
+    0xef, 0xad, 0x9d, 0x87, 0x7a, 0xc5, 0xd9, 0xe0, 0xb2, 0xfd, 0x65, 0xf0, 0xce, 0x7f, 0x9c, 0x96,
+    0x0f, 0x59, 0x0b, 0xe7, 0x80, 0x39, 0x03, 0x2f, 0xe5, 0xe6, 0x73, 0x2c, 0xfc, 0x2d, 0x16, 0xdf
+];
+
+// Ciphertext layout: owner address || nonce || AES-256-GCM ciphertext || tag
+const OWNER_PREFIX_LEN: usize = 20;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+// Envelope layout: grantee key version || nonce || wrapped 32-byte content key || tag
+const ENVELOPE_LEN: usize = 32 + NONCE_LEN + 32 + TAG_LEN;
+
+// Upper bound on notes returned by a single paged listing call
+const MAX_PAGE_SIZE: u64 = 100;
+
+// Upper bound on notes created by a single batch call
+const MAX_BATCH_SIZE: usize = 50;
+
+// Default cap on stored notes per user. Binned and archived notes still take storage, so they
+// count too; only purging frees a slot
+const MAX_NOTES_PER_USER: u64 = 1000;
+
+// EIP-712 domain and message types for createNoteSigned
+const EIP712_NAME: &str = "SecureNotes";
+const EIP712_VERSION: &str = "1";
+const EIP712_DOMAIN_TYPE: &str = "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+const CREATE_NOTE_TYPE: &str = "CreateNote(address author,string title,string content,uint256 nonce)";
+
+// Size limits for note input, in bytes; empty titles and content are valid
+const MAX_TITLE_LEN: usize = 256;
+const MAX_CONTENT_LEN: usize = 8192;
+
+// Size cap on a note's attachment before encryption, in bytes
+const MAX_ATTACHMENT_LEN: usize = 4096;
+
+// Size cap on an off-chain attachment reference (CID), in bytes
+const MAX_ATTACHMENT_CID_LEN: usize = 128;
+
+// Ciphers a user can pick for new ciphertext; both are 256-bit AEADs with 12-byte nonces
+// and 16-byte tags, so the stored layout is the same either way
+const CIPHER_AES_GCM: u8 = 0;
+const CIPHER_CHACHA20_POLY1305: u8 = 1;
+
+// Content types a note can be tagged with; 0 is the default for untyped notes
+const CONTENT_TYPE_PLAIN: u8 = 0;
+const CONTENT_TYPE_MARKDOWN: u8 = 1;
+const CONTENT_TYPE_JSON: u8 = 2;
+
+// Ciphertext bytes per storage chunk
+const NOTE_CHUNK_SIZE: usize = 1024;
+
+// Tag limits per note
+const MAX_TAGS_PER_NOTE: usize = 10;
+const MAX_TAG_LEN: usize = 32;
+
+// Outgoing links per note
+const MAX_LINKS_PER_NOTE: usize = 32;
+
+// Previous versions kept per note; the oldest is dropped beyond this
+const MAX_NOTE_VERSIONS: u64 = 10;
+
+// Reject oversized titles or content instead of silently truncating them
+fn validate_note_input(title: &str, content: &str) {
+    if title.len() > MAX_TITLE_LEN {
+        panic!("Title exceeds maximum length");
+    }
+    if content.len() > MAX_CONTENT_LEN {
+        panic!("Content exceeds maximum length");
+    }
+}
+
+// Control characters other than tab and newline break JSON in the frontend, so titles
+// carrying them are rejected unless the writer opted out
+fn validate_title_chars<SDK: SharedAPI>(sdk: &SDK, writer: &Address, title: &str) {
+    if UserAllowsControlChars::get(sdk, *writer) {
+        return;
+    }
+    if title.bytes().any(|b| b < 0x20 && b != b'\t' && b != b'\n') {
+        panic!("Title contains control characters");
+    }
+}
+
+// Derive the 32-byte AES key from the stored user key, falling back to the caller address
+fn derive_cipher_key(caller_bytes: &[u8], encryption_key: &Bytes) -> [u8; 32] {
+    match try_derive_cipher_key(caller_bytes, encryption_key) {
+        Some(key) => key,
+        None => panic!("Encryption key cannot be empty"),
+    }
+}
+
+// Same as derive_cipher_key, but None when there is no key material at all so the
+// decrypt paths can report an error instead of aborting the call
+fn try_derive_cipher_key(caller_bytes: &[u8], encryption_key: &Bytes) -> Option<[u8; 32]> {
+    let key_material = if encryption_key.is_empty() {
+        caller_bytes
+    } else {
+        encryption_key.as_ref()
+    };
+    if key_material.is_empty() {
+        return None;
+    }
+    
+    // Full-length keys are used as-is, anything else goes through keccak256 as a KDF
+    let mut key = [0u8; 32];
+    if key_material.len() == 32 {
+        key.copy_from_slice(key_material);
+    } else {
+        key.copy_from_slice(keccak256(key_material).as_slice());
+    }
+    Some(key)
+}
+
+// Compare two byte strings without stopping at the first difference, so the time taken
+// doesn't reveal how many leading bytes match. Only the lengths (which aren't secret) short-circuit
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= x ^ y;
+    }
+    diff == 0
+}
+
+// Build a 96-bit nonce from the block timestamp and the low 32 bits of the user's counter
+fn build_nonce(block_timestamp: u64, counter: U256) -> [u8; NONCE_LEN] {
+    let counter_bytes = counter.to_be_bytes::<32>();
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[0..8].copy_from_slice(&block_timestamp.to_be_bytes());
+    nonce[8..12].copy_from_slice(&counter_bytes[28..32]);
+    nonce
+}
+
 
 // Helper to emit events with data
 fn emit_event<SDK: SharedAPI>(sdk: &mut SDK, event_sig: [u8; 32], data: Bytes, topics: &[fluentbase_sdk::B256]) {
@@ -85,6 +426,116 @@ fn emit_event<SDK: SharedAPI>(sdk: &mut SDK, event_sig: [u8; 32], data: Bytes, t
     sdk.emit_log(data, &all_topics);
 }
 
+// ABI-encode the (string title, uint256 contentLength, uint256 timestamp) data of NoteCreated
+fn encode_note_event_data(title: &str, content_len: usize, timestamp: U256) -> Bytes {
+    let padded_len = title.len().div_ceil(32) * 32;
+    let mut data = Vec::with_capacity(128 + padded_len);
+    
+    // Head: offset of the dynamic title, then the two static words
+    data.extend_from_slice(&U256::from(96).to_be_bytes::<32>());
+    data.extend_from_slice(&U256::from(content_len).to_be_bytes::<32>());
+    data.extend_from_slice(&timestamp.to_be_bytes::<32>());
+    
+    // Tail: title length followed by its bytes, zero-padded to a full word
+    data.extend_from_slice(&U256::from(title.len()).to_be_bytes::<32>());
+    data.extend_from_slice(title.as_bytes());
+    data.resize(128 + padded_len, 0);
+    
+    Bytes::from(data)
+}
+
+// ABI-encode the (string title, uint256 contentLength, uint256 timestamp, string previousTitle)
+// data of NoteUpdated, so indexers can follow renames from logs alone
+fn encode_note_updated_data(title: &str, content_len: usize, timestamp: U256, previous_title: &str) -> Bytes {
+    let title_padded = title.len().div_ceil(32) * 32;
+    let previous_padded = previous_title.len().div_ceil(32) * 32;
+    let mut data = Vec::with_capacity(192 + title_padded + previous_padded);
+    
+    // Head: offsets of the two dynamic strings around the two static words
+    data.extend_from_slice(&U256::from(128).to_be_bytes::<32>());
+    data.extend_from_slice(&U256::from(content_len).to_be_bytes::<32>());
+    data.extend_from_slice(&timestamp.to_be_bytes::<32>());
+    data.extend_from_slice(&U256::from(160 + title_padded).to_be_bytes::<32>());
+    
+    // Tail: each string as its length followed by its bytes, zero-padded to a full word
+    data.extend_from_slice(&U256::from(title.len()).to_be_bytes::<32>());
+    data.extend_from_slice(title.as_bytes());
+    data.resize(160 + title_padded, 0);
+    data.extend_from_slice(&U256::from(previous_title.len()).to_be_bytes::<32>());
+    data.extend_from_slice(previous_title.as_bytes());
+    data.resize(192 + title_padded + previous_padded, 0);
+    
+    Bytes::from(data)
+}
+
+// Notes an owner may store: the admin override if set, otherwise MAX_NOTES_PER_USER
+fn note_limit<SDK: SharedAPI>(sdk: &SDK, owner: &Address) -> U256 {
+    let limit = UserNoteLimit::get(sdk, *owner);
+    if limit == U256::from(0) { U256::from(MAX_NOTES_PER_USER) } else { limit }
+}
+
+// Revert unless `owner` has room for `additional` more notes under their limit
+fn check_note_capacity<SDK: SharedAPI>(sdk: &SDK, owner: &Address, additional: usize) {
+    let limit = note_limit(sdk, owner);
+    
+    let count = UserNotesCount::get(sdk, *owner);
+    if count >= limit || limit - count < U256::from(additional) {
+        panic!("Note limit reached");
+    }
+}
+
+// Left-pad an address into a 32-byte indexed event topic
+fn address_topic(address: &Address) -> fluentbase_sdk::B256 {
+    let mut padded = [0u8; 32];
+    padded[12..32].copy_from_slice(address.as_slice());
+    fluentbase_sdk::B256::from(padded)
+}
+
+// EIP-712 domain separator for this deployment:
+// keccak256(abi.encode(EIP712Domain typehash, keccak256(name), keccak256(version), chainId, contract))
+fn eip712_domain_separator(chain_id: u64, contract: &Address) -> fluentbase_sdk::B256 {
+    let mut encoded = Vec::with_capacity(5 * 32);
+    encoded.extend_from_slice(keccak256(EIP712_DOMAIN_TYPE.as_bytes()).as_slice());
+    encoded.extend_from_slice(keccak256(EIP712_NAME.as_bytes()).as_slice());
+    encoded.extend_from_slice(keccak256(EIP712_VERSION.as_bytes()).as_slice());
+    encoded.extend_from_slice(&U256::from(chain_id).to_be_bytes::<32>());
+    encoded.extend_from_slice(address_topic(contract).as_slice());
+    keccak256(&encoded)
+}
+
+// EIP-712 digest of a CreateNote(author, title, content, nonce) message, what createNoteSigned
+// expects the author to have signed
+fn signed_note_digest(chain_id: u64, contract: &Address, author: &Address, title: &str, content: &str, nonce: U256) -> fluentbase_sdk::B256 {
+    let mut encoded = Vec::with_capacity(5 * 32);
+    encoded.extend_from_slice(keccak256(CREATE_NOTE_TYPE.as_bytes()).as_slice());
+    encoded.extend_from_slice(address_topic(author).as_slice());
+    encoded.extend_from_slice(keccak256(title.as_bytes()).as_slice());
+    encoded.extend_from_slice(keccak256(content.as_bytes()).as_slice());
+    encoded.extend_from_slice(&nonce.to_be_bytes::<32>());
+    
+    let mut message = Vec::with_capacity(2 + 32 + 32);
+    message.extend_from_slice(b"\x19\x01");
+    message.extend_from_slice(eip712_domain_separator(chain_id, contract).as_slice());
+    message.extend_from_slice(keccak256(&encoded).as_slice());
+    keccak256(&message)
+}
+
+// Recover the address behind a 65-byte `r || s || v` signature, v being 0/1 or 27/28
+fn recover_signer(digest: &fluentbase_sdk::B256, signature: &[u8]) -> Option<Address> {
+    if signature.len() != 65 {
+        return None;
+    }
+    let sig = Signature::from_slice(&signature[..64]).ok()?;
+    let v = signature[64];
+    let recovery_id = RecoveryId::from_byte(if v >= 27 { v - 27 } else { v })?;
+    let key = VerifyingKey::recover_from_prehash(digest.as_slice(), &sig, recovery_id).ok()?;
+    
+    // The address is the last 20 bytes of the hash of the uncompressed key without its 0x04 tag
+    let point = key.to_encoded_point(false);
+    let hash = keccak256(&point.as_bytes()[1..]);
+    Some(Address::from_slice(&hash[12..]))
+}
+
 
 // Store a note
 fn store_note<SDK: SharedAPI>(sdk: &mut SDK, _owner: &Address, note_id: &U256, note: &Note) {
@@ -96,474 +547,6822 @@ fn store_note<SDK: SharedAPI>(sdk: &mut SDK, _owner: &Address, note_id: &U256, n
     // Store owner
     NoteOwner::set(sdk, *note_id, note.owner);
     
-    // Store content
-    NoteContent::set(sdk, *note_id, note.encrypted_content.clone());
+    // Store content, keeping the owner's storage total in step with its size
+    let old_len = load_note_content(sdk, note_id).len();
+    let used = UserStorageBytes::get(sdk, note.owner);
+    UserStorageBytes::set(sdk, note.owner, used + U256::from(note.encrypted_content.len()) - U256::from(old_len));
+    store_note_content(sdk, note_id, &note.encrypted_content);
     
-    // Store timestamp
-    NoteTimestamp::set(sdk, *note_id, note.timestamp);
+    // Store creation and last-modified times
+    NoteCreatedAt::set(sdk, *note_id, note.created_at);
+    NoteUpdatedAt::set(sdk, *note_id, note.updated_at);
     
     // Store title
     NoteTitle::set(sdk, *note_id, note.title.clone());
+    
+    // Store the key version the content was encrypted under
+    NotePerKeyId::set(sdk, *note_id, note.key_version);
+    NoteCipherMode::set(sdk, *note_id, U256::from(note.cipher_mode));
+    
+    // The content key may have changed with the content
+    rewrap_envelopes(sdk, note_id);
 }
 
-// Load a note
-fn load_note<SDK: SharedAPI>(sdk: &SDK, owner: &Address, note_id: &U256) -> Option<Note> {
-    // First check if the note_id is less than the user's note count
-    let count = UserNotesCount::get(sdk, *owner);
-    if *note_id >= count {
-        return None;
+// Reset every per-note mapping for a note id back to its default value
+fn clear_note<SDK: SharedAPI>(sdk: &mut SDK, note_id: &U256) {
+    // Release the content bytes from the owner's storage total
+    let owner = NoteOwner::get(sdk, *note_id);
+    if owner != Address::default() {
+        let used = UserStorageBytes::get(sdk, owner);
+        UserStorageBytes::set(sdk, owner, used - U256::from(load_note_content(sdk, note_id).len()));
+        NoteEnvelope::set(sdk, *note_id, owner, Bytes::new());
     }
     
-    // Get owner of the note to check existence and ownership
-    let owner_addr = NoteOwner::get(sdk, *note_id);
+    NoteId::set(sdk, *note_id, U256::from(0));
+    NoteOwner::set(sdk, *note_id, Address::default());
+    store_note_content(sdk, note_id, &Bytes::new());
+    NoteCreatedAt::set(sdk, *note_id, U256::from(0));
+    NoteUpdatedAt::set(sdk, *note_id, U256::from(0));
+    NoteCreatedBlock::set(sdk, *note_id, U256::from(0));
+    NoteTitle::set(sdk, *note_id, String::new());
+    NotePerKeyId::set(sdk, *note_id, U256::from(0));
+    NoteCipherMode::set(sdk, *note_id, U256::from(0));
+    NoteDeleted::set(sdk, *note_id, false);
+    NotePublic::set(sdk, *note_id, false);
+    NoteArchived::set(sdk, *note_id, false);
+    NoteCategory::set(sdk, *note_id, U256::from(0));
+    NotePinned::set(sdk, *note_id, false);
+    NoteIndex::set(sdk, *note_id, U256::from(0));
+    NoteSortOrder::set(sdk, *note_id, U256::from(0));
+    NoteContentHash::set(sdk, *note_id, B256::ZERO);
+    NoteLastEditor::set(sdk, *note_id, Address::default());
+    NoteContentType::set(sdk, *note_id, U256::from(CONTENT_TYPE_PLAIN));
+    NoteColor::set(sdk, *note_id, U256::from(0));
+    NoteAttachment::set(sdk, *note_id, Bytes::new());
+    NoteAttachmentKeyVersion::set(sdk, *note_id, U256::from(0));
+    NoteAttachmentCipherMode::set(sdk, *note_id, U256::from(0));
+    NoteAttachmentCid::set(sdk, *note_id, Bytes::new());
+    NoteLocked::set(sdk, *note_id, false);
+    NoteExpiresAt::set(sdk, *note_id, U256::from(0));
+    store_tags(sdk, note_id, &[]);
+    store_shares(sdk, note_id, &[]);
+    store_links(sdk, note_id, &[]);
+    clear_note_versions(sdk, note_id);
+}
+
+// Give a new note the next global id and append it to the owner's list. The counter is bumped
+// before anything is written under the id, so a nested create can never be handed the same one
+fn allocate_note_id<SDK: SharedAPI>(sdk: &mut SDK, owner: &Address) -> U256 {
+    let note_id = GlobalNoteCounter::get(sdk);
+    GlobalNoteCounter::set(sdk, note_id + U256::from(1));
     
-    // Check if note exists and belongs to the caller
-    let zero_address = Address::default();
-    if owner_addr == zero_address || owner_addr != *owner {
-        return None;
+    let count = UserNotesCount::get(sdk, *owner);
+    UserNoteIds::set(sdk, *owner, count, note_id);
+    NoteIndex::set(sdk, note_id, count);
+    UserNotesCount::set(sdk, *owner, count + U256::from(1));
+    
+    let total = TotalNotes::get(sdk);
+    TotalNotes::set(sdk, total + U256::from(1));
+    
+    // Ids only grow and setNotesOrder hands out positions below the owner's count,
+    // so sorting by the id puts a new note after every existing one
+    NoteSortOrder::set(sdk, note_id, note_id);
+    
+    note_id
+}
+
+// Turn a stored count or list position into a loop bound, reverting instead of truncating
+// when it doesn't fit in a usize
+fn to_loop_bound(value: U256) -> usize {
+    if value > U256::from(usize::MAX) {
+        panic!("Note count out of range");
     }
+    value.to::<usize>()
+}
+
+// Every note id in the owner's list, in list order
+fn load_note_ids<SDK: SharedAPI>(sdk: &SDK, owner: &Address) -> Vec<U256> {
+    let count = to_loop_bound(UserNotesCount::get(sdk, *owner));
     
-    // Load from our mappings which are easier to work with
-    let title = NoteTitle::get(sdk, *note_id);
-    let content = NoteContent::get(sdk, *note_id);
-    let timestamp = NoteTimestamp::get(sdk, *note_id);
+    let mut ids = Vec::new();
+    for index in 0..count {
+        ids.push(UserNoteIds::get(sdk, *owner, U256::from(index)));
+    }
     
-    Some(Note {
-        id: *note_id,
-        owner: owner_addr,
-        encrypted_content: content,
-        timestamp,
-        title,
-    })
+    ids
 }
 
-// Get all notes for an owner
-fn get_all_notes<SDK: SharedAPI>(sdk: &SDK, owner: &Address) -> Vec<Note> {
+// Remove a note for good: its id is retired and the owner's last note takes its place in the list
+fn remove_note<SDK: SharedAPI>(sdk: &mut SDK, owner: &Address, removed_note: &Note) {
+    let note_id = removed_note.id;
+    let index = NoteIndex::get(sdk, note_id);
+    unindex_title(sdk, owner, &removed_note.title, note_id);
+    
+    // Binned notes already left the total
+    if !NoteDeleted::get(sdk, note_id) {
+        let total = TotalNotes::get(sdk);
+        TotalNotes::set(sdk, total - U256::from(1));
+    }
+    clear_note(sdk, &note_id);
+    
+    // Other notes keep their ids; only the last list entry moves into the gap
     let count = UserNotesCount::get(sdk, *owner);
-    let count_usize = count.as_limbs()[0] as usize;
+    let last_index = count - U256::from(1);
+    if index != last_index {
+        let last_id = UserNoteIds::get(sdk, *owner, last_index);
+        UserNoteIds::set(sdk, *owner, index, last_id);
+        NoteIndex::set(sdk, last_id, index);
+    }
+    UserNoteIds::set(sdk, *owner, last_index, U256::from(0));
+    
+    // Update count
+    UserNotesCount::set(sdk, *owner, last_index);
+}
+
+// Keep a note's current content as its newest version, dropping the oldest past the cap.
+// Versions are owner-only, so they're sealed under the owner's key rather than the note's
+// content key, which grantees hold
+fn push_note_version<SDK: SharedAPI>(sdk: &mut SDK, note: &Note) {
+    let note_id = note.id;
     
-    let mut notes = Vec::with_capacity(count_usize);
-    for i in 0..count_usize {
-        let note_id = U256::from(i);
-        if let Some(note) = load_note(sdk, owner, &note_id) {
-            notes.push(note);
+    // Content that no longer opens has nothing worth keeping
+    let content = match open_with_envelope(sdk, &note.owner, note) {
+        Ok(content) => content,
+        Err(_) => return,
+    };
+    let encrypted = encrypt_as_owner(sdk, &note.owner, content.as_bytes());
+    
+    let mut count = NoteVersionCount::get(sdk, note_id);
+    
+    // Shift everything down a slot so version 0 stays the oldest one kept
+    if count >= U256::from(MAX_NOTE_VERSIONS) {
+        let mut index = U256::from(1);
+        while index < count {
+            let content = NoteVersionContent::get(sdk, note_id, index);
+            let key_version = NoteVersionKeyId::get(sdk, note_id, index);
+            let cipher_mode = NoteVersionCipherMode::get(sdk, note_id, index);
+            NoteVersionContent::set(sdk, note_id, index - U256::from(1), content);
+            NoteVersionKeyId::set(sdk, note_id, index - U256::from(1), key_version);
+            NoteVersionCipherMode::set(sdk, note_id, index - U256::from(1), cipher_mode);
+            index += U256::from(1);
         }
+        count -= U256::from(1);
     }
     
-    notes
+    NoteVersionContent::set(sdk, note_id, count, encrypted);
+    NoteVersionKeyId::set(sdk, note_id, count, active_key_version(sdk, &note.owner));
+    NoteVersionCipherMode::set(sdk, note_id, count, U256::from(cipher_mode_of(sdk, &note.owner)));
+    NoteVersionCount::set(sdk, note_id, count + U256::from(1));
 }
 
-#[derive(Contract)]
-struct SecureNotes<SDK> {
-    sdk: SDK,
+// Drop the whole version history of a note
+fn clear_note_versions<SDK: SharedAPI>(sdk: &mut SDK, note_id: &U256) {
+    let count = NoteVersionCount::get(sdk, *note_id);
+    let mut index = U256::from(0);
+    while index < count {
+        NoteVersionContent::set(sdk, *note_id, index, Bytes::new());
+        NoteVersionKeyId::set(sdk, *note_id, index, U256::from(0));
+        NoteVersionCipherMode::set(sdk, *note_id, index, U256::from(0));
+        index += U256::from(1);
+    }
+    NoteVersionCount::set(sdk, *note_id, U256::from(0));
 }
 
-pub trait SecureNotesAPI {
-    // User registration
-    fn register_user(&mut self, encryption_key: Bytes);
+// Re-seal every kept version of a note under the owner's active key, for key rotation
+fn rekey_note_versions<SDK: SharedAPI>(sdk: &mut SDK, note: &Note) -> Result<(), DecryptError> {
+    let note_id = note.id;
+    let count = NoteVersionCount::get(sdk, note_id);
+    let mut index = U256::from(0);
+    while index < count {
+        let key_version = NoteVersionKeyId::get(sdk, note_id, index);
+        let cipher_mode = NoteVersionCipherMode::get(sdk, note_id, index).to::<u8>();
+        let encrypted = NoteVersionContent::get(sdk, note_id, index);
+        let content = open_ciphertext(sdk, &note.owner, key_version, cipher_mode, &encrypted)?;
+        
+        let resealed = encrypt_as_owner(sdk, &note.owner, content.as_bytes());
+        NoteVersionContent::set(sdk, note_id, index, resealed);
+        NoteVersionKeyId::set(sdk, note_id, index, active_key_version(sdk, &note.owner));
+        NoteVersionCipherMode::set(sdk, note_id, index, U256::from(cipher_mode_of(sdk, &note.owner)));
+        index += U256::from(1);
+    }
+    Ok(())
+}
+
+// Reassemble a note's ciphertext from its chunks
+fn load_note_content<SDK: SharedAPI>(sdk: &SDK, note_id: &U256) -> Bytes {
+    let count = NoteChunkCount::get(sdk, *note_id);
     
-    // Note CRUD operations
-    fn create_note(&mut self, title: String, content: String) -> U256;
-    fn get_note(&self, note_id: U256) -> (String, String, U256);
-    fn update_note(&mut self, note_id: U256, title: String, content: String);
-    fn delete_note(&mut self, note_id: U256);
+    let mut content = Vec::new();
+    let mut index = U256::from(0);
+    while index < count {
+        content.extend_from_slice(&NoteContentChunk::get(sdk, *note_id, index));
+        index += U256::from(1);
+    }
     
-    // Note listing
-    fn get_note_count(&self) -> U256;
-    fn get_notes_list(&self) -> (Vec<U256>, Vec<String>, Vec<U256>);
+    Bytes::from(content)
+}
+
+// Replace a note's ciphertext, clearing chunks left over from longer content
+fn store_note_content<SDK: SharedAPI>(sdk: &mut SDK, note_id: &U256, content: &Bytes) {
+    let old_count = NoteChunkCount::get(sdk, *note_id);
     
-    // Encryption key management
-    fn update_encryption_key(&mut self, new_key: Bytes);
+    let mut count = U256::from(0);
+    for chunk in content.chunks(NOTE_CHUNK_SIZE) {
+        NoteContentChunk::set(sdk, *note_id, count, Bytes::from(chunk.to_vec()));
+        count += U256::from(1);
+    }
     
-    // Encryption operations (previously in separate contract)
-    fn encrypt_note(&self, content: String) -> Bytes;
-    fn decrypt_note(&self, encrypted_content: Bytes) -> String;
+    let mut index = count;
+    while index < old_count {
+        NoteContentChunk::set(sdk, *note_id, index, Bytes::new());
+        index += U256::from(1);
+    }
+    NoteChunkCount::set(sdk, *note_id, count);
+}
+
+// Read the tag list of a note
+fn load_tags<SDK: SharedAPI>(sdk: &SDK, note_id: &U256) -> Vec<String> {
+    let count = NoteTagCount::get(sdk, *note_id);
     
-    // For compatibility with previous Solidity contract
-    fn get_encryption_contract_address(&self) -> Address;
+    let mut tags = Vec::new();
+    let mut index = U256::from(0);
+    while index < count {
+        tags.push(NoteTag::get(sdk, *note_id, index));
+        index += U256::from(1);
+    }
+    
+    tags
 }
 
-#[router(mode = "solidity")]
-impl<SDK: SharedAPI> SecureNotesAPI for SecureNotes<SDK> {
-    #[function_id("registerUser(bytes)")]
-    fn register_user(&mut self, encryption_key: Bytes) {
-        let caller = self.sdk.context().contract_caller();
-        
-        // Set the encryption key if provided
-        if !encryption_key.is_empty() {
-            UserEncryptionKeys::set(&mut self.sdk, caller, encryption_key);
-        }
-        
-        // Create caller address topic for indexed event parameter
-        let caller_bytes = caller.to_vec();
-        let mut padded_caller = [0u8; 32];
-        if caller_bytes.len() >= 20 {
-            padded_caller[12..32].copy_from_slice(&caller_bytes[0..20]);
-        }
-        let caller_topic = fluentbase_sdk::B256::from(padded_caller);
-        
-        // Emit registration event
-        emit_event(&mut self.sdk, EVENT_USER_REGISTERED, Bytes::new(), &[caller_topic]);
+// Replace the tag list of a note, clearing any leftover entries from a longer list
+fn store_tags<SDK: SharedAPI>(sdk: &mut SDK, note_id: &U256, tags: &[String]) {
+    let old_count = NoteTagCount::get(sdk, *note_id);
+    
+    for (i, tag) in tags.iter().enumerate() {
+        NoteTag::set(sdk, *note_id, U256::from(i), tag.clone());
+    }
+    let mut index = U256::from(tags.len());
+    while index < old_count {
+        NoteTag::set(sdk, *note_id, index, String::new());
+        index += U256::from(1);
     }
     
-    #[function_id("createNote(string,string)")]
-    fn create_note(&mut self, title: String, content: String) -> U256 {
-        let caller = self.sdk.context().contract_caller();
-        
-        // Auto-register if not registered
-        if UserEncryptionKeys::get(&self.sdk, caller).is_empty() {
-            UserEncryptionKeys::set(&mut self.sdk, caller, Bytes::new());
-            
-            // Create caller address topic for indexed event parameter
-            let caller_bytes = caller.to_vec();
-            let mut padded_caller = [0u8; 32];
-            if caller_bytes.len() >= 20 {
-                padded_caller[12..32].copy_from_slice(&caller_bytes[0..20]);
-            }
-            let caller_topic = fluentbase_sdk::B256::from(padded_caller);
-            
-            emit_event(&mut self.sdk, EVENT_USER_REGISTERED, Bytes::new(), &[caller_topic]);
-        }
-        
-        // Encrypt the content
-        let encrypted_content = self.encrypt_note(content);
-        
-        // Get existing notes count
-        let count = UserNotesCount::get(&self.sdk, caller);
-        let note_id = count;
-        
-        // Create new note
-        let timestamp = U256::from(self.sdk.context().block_timestamp());
-        
-        let new_note = Note {
-            id: note_id,
-            owner: caller,
-            encrypted_content,
-            timestamp,
-            title: title.clone(),
-        };
-        
-        // Store the note
-        store_note(&mut self.sdk, &caller, &note_id, &new_note);
-        
-        // Update count
-        UserNotesCount::set(&mut self.sdk, caller, count + U256::from(1));
-        
-        // Create topics for indexed parameters
-        let caller_bytes = caller.to_vec();
-        let mut padded_caller = [0u8; 32];
-        if caller_bytes.len() >= 20 {
-            padded_caller[12..32].copy_from_slice(&caller_bytes[0..20]);
-        }
-        let caller_topic = fluentbase_sdk::B256::from(padded_caller);
-        
-        let note_id_bytes = note_id.to_be_bytes::<32>();
-        let note_id_topic = fluentbase_sdk::B256::from(note_id_bytes);
-        
-        // Encode title as event data - create owned bytes to avoid lifetime issues
-        let title_data = Bytes::from(title.clone().into_bytes());
-        
-        // Emit event with indexed parameters and data
-        emit_event(&mut self.sdk, EVENT_NOTE_CREATED, title_data, &[caller_topic, note_id_topic]);
-        
-        note_id
+    NoteTagCount::set(sdk, *note_id, U256::from(tags.len()));
+}
+
+// Read the ids a note links to
+fn load_links<SDK: SharedAPI>(sdk: &SDK, note_id: &U256) -> Vec<U256> {
+    let count = NoteLinkCount::get(sdk, *note_id);
+    
+    let mut links = Vec::new();
+    let mut index = U256::from(0);
+    while index < count {
+        links.push(NoteLinkAt::get(sdk, *note_id, index));
+        index += U256::from(1);
     }
     
-    #[function_id("getNote(uint256)")]
-    fn get_note(&self, note_id: U256) -> (String, String, U256) {
-        let caller = self.sdk.context().contract_caller();
-        
-        if let Some(note) = load_note(&self.sdk, &caller, &note_id) {
-            // Decrypt content
-            let decrypted_content = self.decrypt_note(note.encrypted_content.clone());
-            
-            (note.title.clone(), decrypted_content, note.timestamp)
-        } else {
-            (String::from(""), String::from("Note does not exist"), U256::from(0))
-        }
+    links
+}
+
+// Replace the outgoing links of a note
+fn store_links<SDK: SharedAPI>(sdk: &mut SDK, note_id: &U256, links: &[U256]) {
+    let old_count = NoteLinkCount::get(sdk, *note_id);
+    
+    for (i, link) in links.iter().enumerate() {
+        NoteLinkAt::set(sdk, *note_id, U256::from(i), *link);
+    }
+    let mut index = U256::from(links.len());
+    while index < old_count {
+        NoteLinkAt::set(sdk, *note_id, index, U256::from(0));
+        index += U256::from(1);
     }
     
-    #[function_id("updateNote(uint256,string,string)")]
-    fn update_note(&mut self, note_id: U256, title: String, content: String) {
-        let caller = self.sdk.context().contract_caller();
-        
-        // Auto-register if not registered
-        if UserEncryptionKeys::get(&self.sdk, caller).is_empty() {
-            UserEncryptionKeys::set(&mut self.sdk, caller, Bytes::new());
-            
-            // Create caller address topic for indexed event parameter
-            let caller_bytes = caller.to_vec();
-            let mut padded_caller = [0u8; 32];
-            if caller_bytes.len() >= 20 {
-                padded_caller[12..32].copy_from_slice(&caller_bytes[0..20]);
+    NoteLinkCount::set(sdk, *note_id, U256::from(links.len()));
+}
+
+// Read the addresses a note is shared with
+fn load_shares<SDK: SharedAPI>(sdk: &SDK, note_id: &U256) -> Vec<Address> {
+    let count = NoteShareCount::get(sdk, *note_id);
+    
+    let mut readers = Vec::new();
+    let mut index = U256::from(0);
+    while index < count {
+        readers.push(NoteShareAt::get(sdk, *note_id, index));
+        index += U256::from(1);
+    }
+    
+    readers
+}
+
+// Replace the share list of a note, keeping the NoteSharedWith lookup and each
+// grantee's shared-with-me list in sync
+fn store_shares<SDK: SharedAPI>(sdk: &mut SDK, note_id: &U256, readers: &[Address]) {
+    let old_readers = load_shares(sdk, note_id);
+    for reader in &old_readers {
+        NoteSharedWith::set(sdk, *note_id, *reader, false);
+        if !readers.contains(reader) {
+            NoteEnvelope::set(sdk, *note_id, *reader, Bytes::new());
+            NoteEditors::set(sdk, *note_id, *reader, false);
+            remove_shared_with_me(sdk, reader, note_id);
+        }
+    }
+    
+    for (i, reader) in readers.iter().enumerate() {
+        NoteShareAt::set(sdk, *note_id, U256::from(i), *reader);
+        NoteSharedWith::set(sdk, *note_id, *reader, true);
+        if !old_readers.contains(reader) {
+            add_shared_with_me(sdk, reader, note_id);
+        }
+    }
+    let mut index = U256::from(readers.len());
+    while index < U256::from(old_readers.len()) {
+        NoteShareAt::set(sdk, *note_id, index, Address::default());
+        index += U256::from(1);
+    }
+    
+    NoteShareCount::set(sdk, *note_id, U256::from(readers.len()));
+}
+
+// Add a note to the end of a grantee's shared-with-me list
+fn add_shared_with_me<SDK: SharedAPI>(sdk: &mut SDK, grantee: &Address, note_id: &U256) {
+    let count = SharedWithMeCount::get(sdk, *grantee);
+    SharedWithMeAt::set(sdk, *grantee, count, *note_id);
+    SharedWithMeIndex::set(sdk, *note_id, *grantee, count + U256::from(1));
+    SharedWithMeCount::set(sdk, *grantee, count + U256::from(1));
+}
+
+// Drop a note from a grantee's shared-with-me list; the last entry takes its place
+fn remove_shared_with_me<SDK: SharedAPI>(sdk: &mut SDK, grantee: &Address, note_id: &U256) {
+    let position = SharedWithMeIndex::get(sdk, *note_id, *grantee);
+    if position == U256::from(0) {
+        return;
+    }
+    let index = position - U256::from(1);
+    
+    let last_index = SharedWithMeCount::get(sdk, *grantee) - U256::from(1);
+    if index != last_index {
+        let last_id = SharedWithMeAt::get(sdk, *grantee, last_index);
+        SharedWithMeAt::set(sdk, *grantee, index, last_id);
+        SharedWithMeIndex::set(sdk, last_id, *grantee, position);
+    }
+    SharedWithMeAt::set(sdk, *grantee, last_index, U256::from(0));
+    SharedWithMeIndex::set(sdk, *note_id, *grantee, U256::from(0));
+    SharedWithMeCount::set(sdk, *grantee, last_index);
+}
+
+// Whether a visible note of `owner` carries `tag`
+fn is_tagged_note_of<SDK: SharedAPI>(sdk: &SDK, owner: &Address, note_id: &U256, tag: &str) -> bool {
+    load_note(sdk, owner, note_id).is_some() && load_tags(sdk, note_id).iter().any(|t| t == tag)
+}
+
+// Point the owner's title index at a note
+fn index_title<SDK: SharedAPI>(sdk: &mut SDK, owner: &Address, title: &str, note_id: U256) {
+    TitleToNoteId::set(sdk, *owner, keccak256(title.as_bytes()), note_id + U256::from(1));
+}
+
+// Drop the title index entry if it still points at this note; returns whether it did
+fn unindex_title<SDK: SharedAPI>(sdk: &mut SDK, owner: &Address, title: &str, note_id: U256) -> bool {
+    let title_hash = keccak256(title.as_bytes());
+    if TitleToNoteId::get(sdk, *owner, title_hash) != note_id + U256::from(1) {
+        return false;
+    }
+    TitleToNoteId::set(sdk, *owner, title_hash, U256::from(0));
+    true
+}
+
+// Load a stored note, including soft-deleted ones
+fn load_stored_note<SDK: SharedAPI>(sdk: &SDK, owner: &Address, note_id: &U256) -> Option<Note> {
+    // Check if note exists and belongs to the caller
+    if !is_stored_note_of(sdk, owner, note_id) {
+        return None;
+    }
+    let owner_addr = *owner;
+    
+    // Load from our mappings which are easier to work with
+    let title = NoteTitle::get(sdk, *note_id);
+    let content = load_note_content(sdk, note_id);
+    let created_at = NoteCreatedAt::get(sdk, *note_id);
+    let updated_at = NoteUpdatedAt::get(sdk, *note_id);
+    let key_version = NotePerKeyId::get(sdk, *note_id);
+    let cipher_mode = NoteCipherMode::get(sdk, *note_id).to::<u8>();
+    
+    Some(Note {
+        id: *note_id,
+        owner: owner_addr,
+        encrypted_content: content,
+        created_at,
+        updated_at,
+        title,
+        key_version,
+        cipher_mode,
+    })
+}
+
+// Load a note that is visible to its owner (not soft-deleted)
+fn load_note<SDK: SharedAPI>(sdk: &SDK, owner: &Address, note_id: &U256) -> Option<Note> {
+    if !is_visible_note_of(sdk, owner, note_id) {
+        return None;
+    }
+    load_stored_note(sdk, owner, note_id)
+}
+
+// Whether `note_id` is stored (binned or not) and belongs to `owner`
+fn is_stored_note_of<SDK: SharedAPI>(sdk: &SDK, owner: &Address, note_id: &U256) -> bool {
+    let owner_addr = NoteOwner::get(sdk, *note_id);
+    owner_addr != Address::default() && owner_addr == *owner
+}
+
+// The check load_note applies, without reading the note itself
+fn is_visible_note_of<SDK: SharedAPI>(sdk: &SDK, owner: &Address, note_id: &U256) -> bool {
+    !NoteDeleted::get(sdk, *note_id) && !is_note_expired(sdk, note_id) && is_stored_note_of(sdk, owner, note_id)
+}
+
+// Whether a note with an expiry has reached it at the current block time
+fn is_note_expired<SDK: SharedAPI>(sdk: &SDK, note_id: &U256) -> bool {
+    let expires_at = NoteExpiresAt::get(sdk, *note_id);
+    expires_at != U256::from(0) && U256::from(sdk.context().block_timestamp()) >= expires_at
+}
+
+// Get every soft-deleted note for an owner
+fn get_deleted_notes_for<SDK: SharedAPI>(sdk: &SDK, owner: &Address) -> Vec<Note> {
+    let mut notes = Vec::new();
+    for note_id in load_note_ids(sdk, owner) {
+        if NoteDeleted::get(sdk, note_id) {
+            if let Some(note) = load_stored_note(sdk, owner, &note_id) {
+                notes.push(note);
             }
-            let caller_topic = fluentbase_sdk::B256::from(padded_caller);
-            
-            emit_event(&mut self.sdk, EVENT_USER_REGISTERED, Bytes::new(), &[caller_topic]);
         }
+    }
+    
+    notes
+}
+
+// Load a note for a reader who is its owner, someone it was shared with, or anyone if it's public
+fn load_note_for_reader<SDK: SharedAPI>(sdk: &SDK, reader: &Address, note_id: &U256) -> Option<Note> {
+    let owner = NoteOwner::get(sdk, *note_id);
+    if owner == Address::default() {
+        return None;
+    }
+    
+    if owner == *reader || NoteSharedWith::get(sdk, *note_id, *reader) || NotePublic::get(sdk, *note_id) {
+        load_note(sdk, &owner, note_id)
+    } else {
+        None
+    }
+}
+
+// Get at most `limit` notes for an owner starting at list position `offset`, clamped to the
+// owner's count. Archived notes are skipped but still use up the range.
+fn get_notes_range<SDK: SharedAPI>(sdk: &SDK, owner: &Address, offset: U256, limit: U256) -> Vec<Note> {
+    let count = UserNotesCount::get(sdk, *owner);
+    if offset >= count {
+        return Vec::new();
+    }
+    
+    // Never read more than a page worth of notes per call
+    let max_page = U256::from(MAX_PAGE_SIZE);
+    let limit = if limit > max_page { max_page } else { limit };
+    let end = if count - offset < limit { count } else { offset + limit };
+    
+    let mut notes = Vec::new();
+    for index in to_loop_bound(offset)..to_loop_bound(end) {
+        let note_id = UserNoteIds::get(sdk, *owner, U256::from(index));
+        if !NoteArchived::get(sdk, note_id) {
+            if let Some(note) = load_note(sdk, owner, &note_id) {
+                notes.push(note);
+            }
+        }
+    }
+    
+    notes
+}
+
+// Listable notes for an owner with pinned ones first, each group in sort order, capped at a page.
+// Archived notes are left out unless `include_archived` is set
+fn get_notes_pinned_first<SDK: SharedAPI>(sdk: &SDK, owner: &Address, include_archived: bool) -> Vec<Note> {
+    let max_page = MAX_PAGE_SIZE as usize;
+    
+    let mut note_ids = load_note_ids(sdk, owner);
+    note_ids.sort_by_key(|note_id| NoteSortOrder::get(sdk, *note_id));
+    
+    let mut pinned = Vec::new();
+    let mut unpinned = Vec::new();
+    for note_id in note_ids {
+        if pinned.len() >= max_page {
+            break;
+        }
+        if include_archived || !NoteArchived::get(sdk, note_id) {
+            if let Some(note) = load_note(sdk, owner, &note_id) {
+                if NotePinned::get(sdk, note_id) {
+                    pinned.push(note);
+                } else if unpinned.len() < max_page {
+                    unpinned.push(note);
+                }
+            }
+        }
+    }
+    
+    pinned.extend(unpinned);
+    pinned.truncate(max_page);
+    pinned
+}
+
+// Split notes into the parallel (ids, titles, updated_at, created_at) vectors used by listings
+fn notes_to_columns(notes: &[Note]) -> (Vec<U256>, Vec<String>, Vec<U256>, Vec<U256>) {
+    let mut ids = Vec::with_capacity(notes.len());
+    let mut titles = Vec::with_capacity(notes.len());
+    let mut timestamps = Vec::with_capacity(notes.len());
+    let mut created = Vec::with_capacity(notes.len());
+    
+    for note in notes {
+        ids.push(note.id);
+        titles.push(note.title.clone());
+        timestamps.push(note.updated_at);
+        created.push(note.created_at);
+    }
+    
+    (ids, titles, timestamps, created)
+}
+
+// Key version new ciphertext is stamped with: the pending version while a rotation runs
+fn active_key_version<SDK: SharedAPI>(sdk: &SDK, owner: &Address) -> U256 {
+    let current = UserKeyVersion::get(sdk, *owner);
+    if PendingEncryptionKeys::get(sdk, *owner).is_empty() {
+        current
+    } else {
+        current + U256::from(1)
+    }
+}
+
+// Look up the key an owner had at a given version
+fn key_for_version<SDK: SharedAPI>(sdk: &SDK, owner: &Address, version: U256) -> Bytes {
+    let current = UserKeyVersion::get(sdk, *owner);
+    if version == current {
+        UserEncryptionKeys::get(sdk, *owner)
+    } else if version > current {
+        // Notes already migrated by an unfinished rotation
+        PendingEncryptionKeys::get(sdk, *owner)
+    } else {
+        UserKeyVersions::get(sdk, *owner, version)
+    }
+}
+
+// Replace an owner's key, archiving the old one so notes encrypted under it stay readable
+fn set_encryption_key<SDK: SharedAPI>(sdk: &mut SDK, owner: &Address, new_key: Bytes) {
+    let current = UserKeyVersion::get(sdk, *owner);
+    let old_key = UserEncryptionKeys::get(sdk, *owner);
+    UserKeyVersions::set(sdk, *owner, current, old_key);
+    UserKeyVersion::set(sdk, *owner, current + U256::from(1));
+    
+    // Only the key hash is logged so other devices can notice the change without learning the key
+    let key_hash = keccak256(&new_key);
+    UserEncryptionKeys::set(sdk, *owner, new_key);
+    emit_event(sdk, EVENT_KEY_UPDATED, Bytes::from(key_hash.to_vec()), &[address_topic(owner)]);
+}
+
+// The 32-byte key derived from an owner's active key (the pending key while a rotation runs,
+// or the address fallback when they have none)
+fn active_cipher_key<SDK: SharedAPI>(sdk: &SDK, owner: &Address) -> [u8; 32] {
+    let encryption_key = key_for_version(sdk, owner, active_key_version(sdk, owner));
+    // The address fallback is public information, so strict mode refuses it
+    if encryption_key.is_empty() && RequireEncryptionKey::get(sdk) {
+        panic!("Encryption key not set, call registerUser with a key first");
+    }
+    derive_cipher_key(owner.as_slice(), &encryption_key)
+}
+
+// Encrypt content for an owner with their active key
+fn encrypt_as_owner<SDK: SharedAPI>(sdk: &mut SDK, owner: &Address, content: &[u8]) -> Bytes {
+    let key = active_cipher_key(sdk, owner);
+    seal_for_owner(sdk, owner, &key, content)
+}
+
+// Encrypt a note's content under a fresh content key of its own and wrap that key for the
+// owner; store_note wraps it for the grantees. A share then only ever hands out this note's key
+fn seal_note_content<SDK: SharedAPI>(sdk: &mut SDK, owner: &Address, note_id: &U256, content: &[u8]) -> Bytes {
+    let content_key = fresh_note_key(sdk, owner, note_id);
+    wrap_note_key(sdk, note_id, owner, &content_key);
+    seal_for_owner(sdk, owner, &content_key, content)
+}
+
+// A new content key for a note. Contract code has no entropy source, so it hashes the owner's
+// derived key with a counter value that is never reused: it can't be predicted without the
+// owner's key, and holding it reveals neither that key nor the key of any other note
+fn fresh_note_key<SDK: SharedAPI>(sdk: &mut SDK, owner: &Address, note_id: &U256) -> [u8; 32] {
+    let counter = UserNonceCounter::get(sdk, *owner);
+    UserNonceCounter::set(sdk, *owner, counter + U256::from(1));
+    
+    let mut material = Vec::with_capacity(3 * 32);
+    material.extend_from_slice(&active_cipher_key(sdk, owner));
+    material.extend_from_slice(&note_id.to_be_bytes::<32>());
+    material.extend_from_slice(&counter.to_be_bytes::<32>());
+    keccak256(&material).0
+}
+
+// Seal `content` under `key` as `owner || nonce || ciphertext || tag` with the owner's cipher
+fn seal_for_owner<SDK: SharedAPI>(sdk: &mut SDK, owner: &Address, key: &[u8; 32], content: &[u8]) -> Bytes {
+    let owner_bytes = owner.to_vec();
+    
+    // Build a unique nonce from the block timestamp and a per-user counter
+    let counter = UserNonceCounter::get(sdk, *owner);
+    UserNonceCounter::set(sdk, *owner, counter + U256::from(1));
+    let nonce = build_nonce(sdk.context().block_timestamp(), counter);
+    
+    // Prepare result buffer with room for ownership data, nonce and content
+    let mut result = Vec::with_capacity(OWNER_PREFIX_LEN + NONCE_LEN + content.len() + TAG_LEN);
+    
+    // Add owner address to encrypted data for ownership verification
+    result.extend_from_slice(&owner_bytes);
+    result.extend_from_slice(&nonce);
+    
+    // Seal with the owner's chosen AEAD, the owner prefix as associated data so it can't be swapped out
+    let ciphertext = aead_seal(cipher_mode_of(sdk, owner), key, &nonce, content, &owner_bytes);
+    result.extend_from_slice(&ciphertext);
+    
+    Bytes::from(result)
+}
+
+// The cipher an owner's new ciphertext is sealed with
+fn cipher_mode_of<SDK: SharedAPI>(sdk: &SDK, owner: &Address) -> u8 {
+    UserCipherMode::get(sdk, *owner).to::<u8>()
+}
+
+// Seal `msg` with the AEAD for `cipher_mode`, returning ciphertext || tag
+fn aead_seal(cipher_mode: u8, key: &[u8; 32], nonce: &[u8], msg: &[u8], aad: &[u8]) -> Vec<u8> {
+    let payload = Payload { msg, aad };
+    let sealed = if cipher_mode == CIPHER_CHACHA20_POLY1305 {
+        ChaCha20Poly1305::new(Key::<ChaCha20Poly1305>::from_slice(key)).encrypt(Nonce::from_slice(nonce), payload)
+    } else {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)).encrypt(Nonce::from_slice(nonce), payload)
+    };
+    match sealed {
+        Ok(ciphertext) => ciphertext,
+        Err(_) => panic!("Encryption failed"),
+    }
+}
+
+// Open ciphertext || tag with the AEAD for `cipher_mode`; None if the tag doesn't verify
+fn aead_open(cipher_mode: u8, key: &[u8; 32], nonce: &[u8], msg: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+    let payload = Payload { msg, aad };
+    let opened = if cipher_mode == CIPHER_CHACHA20_POLY1305 {
+        ChaCha20Poly1305::new(Key::<ChaCha20Poly1305>::from_slice(key)).decrypt(Nonce::from_slice(nonce), payload)
+    } else {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)).decrypt(Nonce::from_slice(nonce), payload)
+    };
+    opened.ok()
+}
+
+// Try to open `owner || nonce || ciphertext || tag` with one candidate key
+fn open_with_user_key(cipher_mode: u8, owner_bytes: &[u8], encryption_key: &Bytes, data: &[u8]) -> Option<Vec<u8>> {
+    open_with_key(cipher_mode, &try_derive_cipher_key(owner_bytes, encryption_key)?, data)
+}
+
+// Same as open_with_user_key with an already derived key
+fn open_with_key(cipher_mode: u8, key: &[u8; 32], data: &[u8]) -> Option<Vec<u8>> {
+    // Decrypt and verify the tag (the owner prefix is authenticated as AAD), so any
+    // bit flip in the prefix, nonce, ciphertext or tag is rejected instead of garbling output
+    let nonce = &data[OWNER_PREFIX_LEN..OWNER_PREFIX_LEN + NONCE_LEN];
+    aead_open(cipher_mode, key, nonce, &data[OWNER_PREFIX_LEN + NONCE_LEN..], &data[0..OWNER_PREFIX_LEN])
+}
+
+// Why a ciphertext couldn't be opened; the code is what tryDecryptNote returns
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DecryptError {
+    InvalidFormat = 1,
+    NoPermission = 2,
+    InvalidUtf8 = 3,
+    AuthenticationFailed = 4,
+}
+
+impl DecryptError {
+    fn code(self) -> u8 {
+        self as u8
+    }
+    
+    // Message decryptNote has always returned in place of the plaintext
+    fn message(self) -> &'static str {
+        match self {
+            DecryptError::InvalidFormat => "Error: Invalid data format",
+            DecryptError::NoPermission => "Error: You don't have permission to decrypt this note",
+            DecryptError::InvalidUtf8 => "Error: Decryption failed",
+            DecryptError::AuthenticationFailed => "Error: authentication failed",
+        }
+    }
+}
+
+// Open note ciphertext with the owner's key at `key_version`; callers must have already checked read access
+fn open_ciphertext<SDK: SharedAPI>(sdk: &SDK, owner: &Address, key_version: U256, cipher_mode: u8, encrypted_content: &Bytes) -> Result<String, DecryptError> {
+    let decrypted = open_ciphertext_bytes(sdk, owner, key_version, cipher_mode, encrypted_content)?;
+    
+    // Convert decrypted bytes to string
+    match String::from_utf8(decrypted) {
+        Ok(s) => Ok(s),
+        Err(_) => Err(DecryptError::InvalidUtf8),
+    }
+}
+
+// Same as open_ciphertext for binary plaintext such as attachments
+fn open_ciphertext_bytes<SDK: SharedAPI>(sdk: &SDK, owner: &Address, key_version: U256, cipher_mode: u8, encrypted_content: &Bytes) -> Result<Vec<u8>, DecryptError> {
+    let data = encrypted_content.to_vec();
+    
+    // Validate data format: owner || nonce || ciphertext || tag
+    if data.len() < OWNER_PREFIX_LEN + NONCE_LEN + TAG_LEN {
+        return Err(DecryptError::InvalidFormat);
+    }
+    
+    // Extract the owner address from the encrypted data
+    let stored_address = &data[0..OWNER_PREFIX_LEN];
+    let owner_bytes = owner.to_vec();
+    
+    if !constant_time_eq(stored_address, owner_bytes.as_slice()) {
+        return Err(DecryptError::NoPermission);
+    }
+    
+    // Try the recorded key version first; mid-rotation, already migrated notes open with the pending key
+    let mut decrypted = open_with_user_key(cipher_mode, &owner_bytes, &key_for_version(sdk, owner, key_version), &data);
+    if decrypted.is_none() {
+        let pending_key = PendingEncryptionKeys::get(sdk, *owner);
+        if !pending_key.is_empty() {
+            decrypted = open_with_user_key(cipher_mode, &owner_bytes, &pending_key, &data);
+        }
+    }
+    match decrypted {
+        Some(plaintext) => Ok(plaintext),
+        None => Err(DecryptError::AuthenticationFailed),
+    }
+}
+
+// Encrypt and store a note's attachment under the owner's active key; empty data removes it
+fn store_attachment<SDK: SharedAPI>(sdk: &mut SDK, owner: &Address, note_id: &U256, data: &[u8]) {
+    if data.is_empty() {
+        NoteAttachment::set(sdk, *note_id, Bytes::new());
+        NoteAttachmentKeyVersion::set(sdk, *note_id, U256::from(0));
+        NoteAttachmentCipherMode::set(sdk, *note_id, U256::from(0));
+        return;
+    }
+    
+    let encrypted = encrypt_as_owner(sdk, owner, data);
+    NoteAttachment::set(sdk, *note_id, encrypted);
+    NoteAttachmentKeyVersion::set(sdk, *note_id, active_key_version(sdk, owner));
+    NoteAttachmentCipherMode::set(sdk, *note_id, U256::from(cipher_mode_of(sdk, owner)));
+}
+
+// Decrypt a note's attachment with the owner's key; empty when the note has none
+fn load_attachment<SDK: SharedAPI>(sdk: &SDK, owner: &Address, note_id: &U256) -> Result<Vec<u8>, DecryptError> {
+    let encrypted = NoteAttachment::get(sdk, *note_id);
+    if encrypted.is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    let key_version = NoteAttachmentKeyVersion::get(sdk, *note_id);
+    let cipher_mode = NoteAttachmentCipherMode::get(sdk, *note_id).to::<u8>();
+    open_ciphertext_bytes(sdk, owner, key_version, cipher_mode, &encrypted)
+}
+
+// Decrypt a stored note by id for `reader`. Whose key to use and whether the reader may see
+// the note come from storage (NoteOwner, shares, visibility), never from the ciphertext prefix
+fn decrypt_stored_note<SDK: SharedAPI>(sdk: &SDK, reader: &Address, note_id: &U256) -> Result<String, DecryptError> {
+    let note = match load_note_for_reader(sdk, reader, note_id) {
+        Some(note) => note,
+        None => return Err(DecryptError::NoPermission),
+    };
+    
+    // Grantees open the note with the content key from their own envelope; public readers
+    // go through the owner's
+    if *reader != note.owner && NoteSharedWith::get(sdk, *note_id, *reader) {
+        return open_with_envelope(sdk, reader, &note);
+    }
+    open_with_envelope(sdk, &note.owner, &note)
+}
+
+// The key a note's content is sealed with, recovered from the owner's envelope
+fn note_content_key<SDK: SharedAPI>(sdk: &SDK, note_id: &U256) -> [u8; 32] {
+    let owner = NoteOwner::get(sdk, *note_id);
+    match unwrap_note_key(sdk, note_id, &owner) {
+        Some(content_key) => content_key,
+        None => panic!("Note content key could not be unwrapped"),
+    }
+}
+
+// Re-seal a note's content under a fresh content key and wrap it again for the owner and every
+// grantee, so a key handed out earlier no longer opens it
+fn rekey_note<SDK: SharedAPI>(sdk: &mut SDK, note: &Note) -> Result<(), DecryptError> {
+    let plaintext = open_with_envelope(sdk, &note.owner, note)?;
+    
+    let mut rekeyed = note.clone();
+    rekeyed.encrypted_content = seal_note_content(sdk, &note.owner, &note.id, plaintext.as_bytes());
+    rekeyed.key_version = active_key_version(sdk, &note.owner);
+    rekeyed.cipher_mode = cipher_mode_of(sdk, &note.owner);
+    store_note(sdk, &note.owner, &note.id, &rekeyed);
+    Ok(())
+}
+
+// Binds an envelope to its note and grantee so it can't be replayed elsewhere
+fn envelope_aad(note_id: &U256, grantee: &Address) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(32 + OWNER_PREFIX_LEN);
+    aad.extend_from_slice(&note_id.to_be_bytes::<32>());
+    aad.extend_from_slice(grantee.as_slice());
+    aad
+}
+
+// Wrap a note's content key under the holder's active key (AES-256-GCM). The nonce comes
+// from the holder's counter, which every other encryption under that key also draws from
+fn wrap_note_key<SDK: SharedAPI>(sdk: &mut SDK, note_id: &U256, grantee: &Address, content_key: &[u8; 32]) {
+    let key_version = active_key_version(sdk, grantee);
+    let wrapping_key = derive_cipher_key(grantee.as_slice(), &key_for_version(sdk, grantee, key_version));
+    
+    let counter = UserNonceCounter::get(sdk, *grantee);
+    UserNonceCounter::set(sdk, *grantee, counter + U256::from(1));
+    let nonce = build_nonce(sdk.context().block_timestamp(), counter);
+    
+    let aad = envelope_aad(note_id, grantee);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&wrapping_key));
+    let payload = Payload {
+        msg: content_key,
+        aad: &aad,
+    };
+    let wrapped = match cipher.encrypt(Nonce::from_slice(&nonce), payload) {
+        Ok(wrapped) => wrapped,
+        Err(_) => panic!("Encryption failed"),
+    };
+    
+    let mut envelope = Vec::with_capacity(ENVELOPE_LEN);
+    envelope.extend_from_slice(&key_version.to_be_bytes::<32>());
+    envelope.extend_from_slice(&nonce);
+    envelope.extend_from_slice(&wrapped);
+    NoteEnvelope::set(sdk, *note_id, *grantee, Bytes::from(envelope));
+}
+
+// Recover the content key from a grantee's envelope; None if it is missing or doesn't open
+fn unwrap_note_key<SDK: SharedAPI>(sdk: &SDK, note_id: &U256, grantee: &Address) -> Option<[u8; 32]> {
+    let envelope = NoteEnvelope::get(sdk, *note_id, *grantee);
+    if envelope.len() != ENVELOPE_LEN {
+        return None;
+    }
+    
+    // Old grantee keys stay archived, so an envelope keeps opening after the grantee rotates
+    let key_version = U256::from_be_slice(&envelope[0..32]);
+    let wrapping_key = derive_cipher_key(grantee.as_slice(), &key_for_version(sdk, grantee, key_version));
+    
+    let aad = envelope_aad(note_id, grantee);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&wrapping_key));
+    let payload = Payload {
+        msg: &envelope[32 + NONCE_LEN..],
+        aad: &aad,
+    };
+    let unwrapped = cipher.decrypt(Nonce::from_slice(&envelope[32..32 + NONCE_LEN]), payload).ok()?;
+    
+    let mut content_key = [0u8; 32];
+    content_key.copy_from_slice(&unwrapped);
+    Some(content_key)
+}
+
+// Give every grantee of a note a fresh envelope, after its content was re-encrypted
+fn rewrap_envelopes<SDK: SharedAPI>(sdk: &mut SDK, note_id: &U256) {
+    let grantees = load_shares(sdk, note_id);
+    if grantees.is_empty() {
+        return;
+    }
+    
+    let content_key = note_content_key(sdk, note_id);
+    for grantee in grantees {
+        wrap_note_key(sdk, note_id, &grantee, &content_key);
+    }
+}
+
+// Decrypt a note with the content key from `grantee`'s envelope (the owner's, for the owner)
+fn open_with_envelope<SDK: SharedAPI>(sdk: &SDK, grantee: &Address, note: &Note) -> Result<String, DecryptError> {
+    let data = note.encrypted_content.to_vec();
+    if data.len() < OWNER_PREFIX_LEN + NONCE_LEN + TAG_LEN {
+        return Err(DecryptError::InvalidFormat);
+    }
+    if !constant_time_eq(&data[0..OWNER_PREFIX_LEN], note.owner.as_slice()) {
+        return Err(DecryptError::NoPermission);
+    }
+    
+    let content_key = match unwrap_note_key(sdk, &note.id, grantee) {
+        Some(key) => key,
+        None => return Err(DecryptError::NoPermission),
+    };
+    let decrypted = match open_with_key(note.cipher_mode, &content_key, &data) {
+        Some(plaintext) => plaintext,
+        None => return Err(DecryptError::AuthenticationFailed),
+    };
+    
+    match String::from_utf8(decrypted) {
+        Ok(s) => Ok(s),
+        Err(_) => Err(DecryptError::InvalidUtf8),
+    }
+}
+
+// Decrypt note ciphertext, reporting failures as an error string in place of the content
+fn decrypt_as_owner<SDK: SharedAPI>(sdk: &SDK, owner: &Address, key_version: U256, cipher_mode: u8, encrypted_content: &Bytes) -> String {
+    match open_ciphertext(sdk, owner, key_version, cipher_mode, encrypted_content) {
+        Ok(s) => s,
+        Err(e) => String::from(e.message()),
+    }
+}
+
+#[derive(Contract)]
+struct SecureNotes<SDK> {
+    sdk: SDK,
+}
+
+pub trait SecureNotesAPI {
+    // User registration
+    fn register_user(&mut self, encryption_key: Bytes);
+    // True once the user called registerUser or wrote their first note
+    fn is_registered(&self, user: Address) -> bool;
+    
+    // Note CRUD operations
+    fn create_note(&mut self, title: String, content: String) -> U256;
+    // Same as createNote but records how the content should be rendered (0 plain, 1 markdown, 2 JSON)
+    fn create_note_typed(&mut self, title: String, content: String, content_type: u8) -> U256;
+    fn get_note_content_type(&self, note_id: U256) -> u8;
+    fn set_note_type(&mut self, note_id: U256, content_type: u8);
+    // Self-destructing note: from `expires_at` on it reads as missing everywhere notes are
+    // looked up by id or listed, though it keeps its storage (and quota) until purged
+    fn create_note_with_expiry(&mut self, title: String, content: String, expires_at: U256) -> U256;
+    fn is_expired(&self, note_id: U256) -> bool;
+    // Move or clear (0) the expiry of an unlocked note that hasn't expired yet
+    fn set_note_expiry(&mut self, note_id: U256, expires_at: U256);
+    fn create_notes_batch(&mut self, titles: Vec<String>, contents: Vec<String>) -> Vec<U256>;
+    // Create a note from ciphertext made elsewhere: `nonce || ciphertext || tag` under the caller's
+    // active key (encryptNote output minus its owner prefix, which the contract prepends). It is
+    // only checked against that key; the note is stored sealed under its own content key
+    fn import_encrypted_note(&mut self, title: String, encrypted_content: Bytes) -> U256;
+    // Relayed creation: stores the note under `author`, who signed an EIP-712 CreateNote
+    // (author, title, content, nonce) message off-chain
+    fn create_note_signed(&mut self, author: Address, title: String, content: String, nonce: U256, signature: Bytes) -> U256;
+    fn get_signed_note_nonce(&self, author: Address) -> U256;
+    // EIP-712 helpers so clients can check they hash the same thing createNoteSigned verifies
+    fn domain_separator(&self) -> B256;
+    fn hash_create_note(&self, author: Address, title: String, content: String, nonce: U256) -> B256;
+    // Delegated creation: an approved delegate creates notes owned by `owner`, without a signature per note
+    fn set_create_delegate(&mut self, delegate: Address, allowed: bool);
+    fn is_create_delegate(&self, owner: Address, delegate: Address) -> bool;
+    fn create_note_for(&mut self, owner: Address, title: String, content: String) -> U256;
+    fn get_note(&self, note_id: U256) -> (String, String, U256, U256);
+    // getNote with a separate revert reason for every way a read can fail: missing, not
+    // readable by the caller, binned, expired, or content that doesn't decrypt
+    fn get_note_strict(&self, note_id: U256) -> (String, String, U256, U256);
+    // getNote as a transaction, for access auditing: returns (title, content, updated_at) and
+    // emits NoteAccessed when the reader isn't the owner. Reverts while paused; getNote doesn't
+    fn read_note_logged(&mut self, note_id: U256) -> (String, String, U256);
+    fn try_get_note(&self, note_id: U256) -> (bool, String, String, U256, U256);
+    fn get_note_timestamps(&self, note_id: U256) -> (U256, U256);
+    // Block number the note was created in, 0 if the caller has no such note
+    fn get_note_block(&self, note_id: U256) -> U256;
+    // Owner of a note id, or the zero address if there is none; readable by anyone
+    fn get_note_owner(&self, note_id: U256) -> Address;
+    // (owner, last editor, updated_at, content type) for anyone who can read the note
+    fn get_note_meta(&self, note_id: U256) -> (Address, Address, U256, u8);
+    // Whether getNote would find the note for `owner`, without loading or decrypting it
+    fn note_exists(&self, owner: Address, note_id: U256) -> bool;
+    // (character count, word count) of the decrypted content
+    fn get_note_stats(&self, note_id: U256) -> (U256, U256);
+    // The caller's envelope for a note: `keyVersion || nonce || wrapped content key || tag`, the
+    // content key sealed under the caller's key at that version. Empty for public readers
+    fn get_note_envelope(&self, note_id: U256) -> Bytes;
+    // Stored `owner || nonce || ciphertext || tag` for anyone who can read the note, for clients
+    // that decrypt off-chain; reverts instead of returning empty bytes
+    fn get_note_ciphertext(&self, note_id: U256) -> Bytes;
+    // Owner-only binary attachment (up to MAX_ATTACHMENT_LEN bytes), encrypted under the owner's
+    // key; empty data removes it. It moves with transferNote and is dropped on purge
+    fn set_note_attachment(&mut self, note_id: U256, data: Bytes);
+    fn get_note_attachment(&self, note_id: U256) -> Bytes;
+    // Reference to a blob kept off-chain (IPFS CID or content hash, up to MAX_ATTACHMENT_CID_LEN
+    // bytes). It is stored unencrypted and readable by anyone who can read the note; empty clears it
+    fn set_note_attachment_cid(&mut self, note_id: U256, cid: Bytes);
+    fn get_note_attachment_cid(&self, note_id: U256) -> Bytes;
+    // Integrity: plaintext hash recorded on write, and whether the stored ciphertext still matches it
+    fn get_note_hash(&self, note_id: U256) -> B256;
+    fn verify_note_integrity(&self, note_id: U256) -> bool;
+    // Returns the id; reverts when the note doesn't exist or the caller may not edit it
+    fn update_note(&mut self, note_id: U256, title: String, content: String) -> U256;
+    // Rename without touching the ciphertext; no version is recorded
+    fn update_note_title(&mut self, note_id: U256, title: String);
+    // Replace the content and keep the current title
+    fn update_note_content(&mut self, note_id: U256, content: String);
+    // Decrypts the current content, adds `extra` to the end and stores the result like updateNote
+    fn append_to_note(&mut self, note_id: U256, extra: String);
+    
+    // Version history: every update keeps the previous content, version 0 being the oldest kept
+    fn get_note_version_count(&self, note_id: U256) -> U256;
+    fn get_note_version(&self, note_id: U256, version: U256) -> String;
+    // Makes an old version the current content again; the replaced content becomes a new version
+    fn revert_note_to_version(&mut self, note_id: U256, version: U256);
+    
+    // Recycle bin: delete hides the note without touching ids or the count, and can be undone
+    // Returns the id of the binned note; reverts when the caller has no such note
+    fn delete_note(&mut self, note_id: U256) -> U256;
+    // Bins every listed note the caller owns, skipping the rest instead of reverting
+    fn delete_notes_batch(&mut self, note_ids: Vec<U256>);
+    fn restore_note(&mut self, note_id: U256);
+    fn get_deleted_notes(&self) -> (Vec<U256>, Vec<String>, Vec<U256>, Vec<U256>);
+    // Permanently removes the note; other notes keep their ids
+    fn purge_note(&mut self, note_id: U256);
+    // Account reset: purges the caller's notes from the end of their list, at most
+    // MAX_NOTES_PER_USER per call; call again while getNoteCount is above the number of locked
+    // notes, which are kept. Rather than a NoteDeleted per note, each call emits a single
+    // AllNotesDeleted with how many it purged
+    fn delete_all_notes(&mut self);
+    // Frees the storage of expired notes among list positions `offset..offset + limit` (at most a
+    // page). Purging moves later notes into the gaps, so callers should walk from the end of the list.
+    // Returns how many notes were purged
+    fn purge_expired(&mut self, offset: U256, limit: U256) -> U256;
+    
+    // Archive: hides a note from getNotesList without deleting it
+    fn archive_note(&mut self, note_id: U256);
+    fn unarchive_note(&mut self, note_id: U256);
+    fn get_archived_notes(&self) -> (Vec<U256>, Vec<String>, Vec<U256>);
+    
+    // Title lookup (last-writer-wins when titles collide)
+    fn find_note_by_title(&self, title: String) -> (bool, U256);
+    
+    // Note tags
+    fn set_note_tags(&mut self, note_id: U256, tags: Vec<String>);
+    fn get_note_tags(&self, note_id: U256) -> Vec<String>;
+    fn get_notes_by_tag(&self, tag: String) -> Vec<U256>;
+    // How many notes getNotesByTag would return, without building the list
+    fn count_notes_by_tag(&self, tag: String) -> U256;
+    
+    // Note links: directed references between two of the caller's notes; linking twice is a no-op
+    fn link_notes(&mut self, from_id: U256, to_id: U256);
+    fn unlink_notes(&mut self, from_id: U256, to_id: U256);
+    // Outgoing links, leaving out notes that were since deleted, purged or expired
+    fn get_note_links(&self, note_id: U256) -> Vec<U256>;
+    
+    // Note categories (folders), 0 is uncategorized
+    fn set_note_category(&mut self, note_id: U256, category: U256);
+    fn get_notes_by_category(&self, category: U256) -> (Vec<U256>, Vec<String>);
+    
+    // Color label shown by getNotesListFull: 0xRRGGBB in the low three bytes, 0 for none
+    fn set_note_color(&mut self, note_id: U256, color: U256);
+    
+    // Pinned notes
+    fn pin_note(&mut self, note_id: U256);
+    fn unpin_note(&mut self, note_id: U256);
+    fn get_pinned_notes(&self) -> (Vec<U256>, Vec<String>, Vec<U256>, Vec<U256>);
+    
+    // Locking guards a note against accidental edits; deleteNotesBatch and deleteAllNotes skip
+    // locked notes
+    fn lock_note(&mut self, note_id: U256);
+    fn unlock_note(&mut self, note_id: U256);
+    
+    // Manual ordering for getNotesList; must list each of the caller's note ids exactly once
+    fn set_notes_order(&mut self, ordered_ids: Vec<U256>);
+    // Move one note to `new_index` in that order, shifting the notes in between by one.
+    // Positions count every note the caller stores, binned and archived ones included
+    fn reorder_note(&mut self, note_id: U256, new_index: U256);
+    
+    // Hand a note over to another address with an encryption key; returns the id it gets in
+    // the recipient's list. Content, attachment, tags, type, color and expiry move with it;
+    // shares, links, category, pin, archive and public state are the sender's and are dropped.
+    // Locked notes can't move
+    fn transfer_note(&mut self, note_id: U256, to: Address) -> U256;
+    
+    // Note sharing grants read access. Grantees must have set an encryption key; they decrypt
+    // through an envelope wrapped under it
+    fn share_note(&mut self, note_id: U256, with: Address);
+    fn revoke_share(&mut self, note_id: U256, with: Address);
+    // Let a grantee also update the note (but not delete, share or transfer it), or take that back
+    fn set_note_editor(&mut self, note_id: U256, editor: Address, allowed: bool);
+    fn is_note_editor(&self, note_id: U256, editor: Address) -> bool;
+    // (owners, ids) of the notes currently shared with the caller that they can read;
+    // binned and expired notes are left out
+    fn get_shared_with_me(&self) -> (Vec<Address>, Vec<U256>);
+    
+    // Public notes: readable by any caller through getNote/getPublicNote
+    // (raw decryptNote stays owner-only since a ciphertext alone doesn't identify its note)
+    fn set_note_public(&mut self, note_id: U256, public: bool);
+    fn get_public_note(&self, owner: Address, note_id: U256) -> (String, String, U256);
+    // Cross-user read of (title, content, updated_at) for the owner, shared readers and public notes
+    fn get_note_public(&self, owner: Address, note_id: U256) -> (String, String, U256);
+    
+    // Note listing
+    fn get_note_count(&self) -> U256;
+    // Every note the caller has created, including purged ones
+    fn get_lifetime_note_count(&self) -> U256;
+    // First page of notes, pinned ones first
+    fn get_notes_list(&self) -> (Vec<U256>, Vec<String>, Vec<U256>, Vec<U256>);
+    // Like getNotesList but archived notes are included: (ids, titles, updated_at, pinned, archived, colors)
+    fn get_notes_list_full(&self) -> (Vec<U256>, Vec<String>, Vec<U256>, Vec<bool>, Vec<bool>, Vec<U256>);
+    // Same columns as getNotesList plus the pinned flag of each note; `offset` is a position in
+    // the caller's note list, not a note id
+    fn get_notes_page(&self, offset: U256, limit: U256) -> (Vec<U256>, Vec<String>, Vec<U256>, Vec<U256>, Vec<bool>);
+    // Listable notes as (ids, titles, updated_at) ordered by last update, ties in list order.
+    // Reverts past MAX_NOTES_PER_USER notes; use getNotesPage then
+    fn get_notes_sorted(&self, descending: bool) -> (Vec<U256>, Vec<String>, Vec<U256>);
+    // Most recently updated first; shorthand for getNotesSorted(true)
+    fn get_notes_list_by_recent(&self) -> (Vec<U256>, Vec<String>, Vec<U256>);
+    // Ids of notes whose decrypted content contains `query` (case-sensitive). Decrypting is
+    // expensive, so only the getNotesPage window at (offset, limit) is scanned; an empty query
+    // matches every note in the window
+    fn search_notes(&self, query: String, offset: U256, limit: U256) -> Vec<U256>;
+    
+    // Input limits (max title bytes, max content bytes)
+    fn get_limits(&self) -> (U256, U256);
+    
+    // Contract-wide statistics: notes outside the recycle bin, and registered users
+    fn total_notes(&self) -> U256;
+    fn total_users(&self) -> U256;
+    
+    // Ciphertext bytes stored for a user; binned notes count until they're purged
+    fn get_storage_used(&self, user: Address) -> U256;
+    
+    // How many more notes the caller can create; binned and archived notes use up quota
+    fn get_remaining_quota(&self) -> U256;
+    
+    // Contract administration
+    fn owner(&self) -> Address;
+    fn transfer_ownership(&mut self, new_owner: Address);
+    fn pause(&mut self);
+    fn unpause(&mut self);
+    fn paused(&self) -> bool;
+    
+    // Contract owner only: override how many notes a user may store
+    fn set_user_note_limit(&mut self, user: Address, limit: U256);
+    
+    // Contract owner only: when on, encrypting for a user without a key reverts
+    // instead of falling back to their (public) address
+    fn set_require_encryption_key(&mut self, required: bool);
+    fn require_encryption_key(&self) -> bool;
+    
+    // Encryption key management
+    fn update_encryption_key(&mut self, new_key: Bytes);
+    fn rotate_encryption_key(&mut self, new_key: Bytes);
+    fn rotate_encryption_key_range(&mut self, new_key: Bytes, limit: U256) -> U256;
+    // False when the user relies on the address-derived fallback key
+    fn has_encryption_key(&self, user: Address) -> bool;
+    // The caller's own key, for backing it up; reverts when no key is set. Storage is public
+    // anyway, this just gives the frontend a normal ABI call for it
+    fn export_encryption_key(&self) -> Bytes;
+    // (registered, note count, keccak256 of the key or zero without one); the key itself is never returned
+    fn get_user_info(&self, user: Address) -> (bool, U256, B256);
+    // Dashboard bundle for the caller: (live note count, lifetime note count, has key)
+    fn get_user_profile(&self) -> (U256, U256, bool);
+    // Titles with control characters (below 0x20, except tab and newline) revert unless the
+    // writer opts out here, e.g. for binary-ish titles the frontend escapes itself
+    fn set_allow_control_chars(&mut self, allowed: bool);
+    fn allows_control_chars(&self, user: Address) -> bool;
+    // Cipher for the caller's new ciphertext: 0 AES-256-GCM (default), 1 ChaCha20-Poly1305.
+    // Stored notes keep opening with the cipher they were written with; raw ciphertext passed
+    // to decryptNote/tryDecryptNote is opened with the current one
+    fn set_cipher_mode(&mut self, mode: u8);
+    fn get_cipher_mode(&self, user: Address) -> u8;
+    
+    // Encryption operations (previously in separate contract)
+    fn encrypt_note(&mut self, content: String) -> Bytes;
+    // Caller-supplied ciphertext under the caller's own key; says nothing about stored notes
+    fn decrypt_note(&self, encrypted_content: Bytes) -> String;
+    // (status, plaintext): 0 ok, 1 bad format, 2 no permission, 3 invalid UTF-8,
+    // 4 authentication failed; the plaintext is empty unless the status is 0
+    fn try_decrypt_note(&self, encrypted_content: Bytes) -> (u8, String);
+    
+    // For compatibility with previous Solidity contract
+    fn get_encryption_contract_address(&self) -> Address;
+}
+
+#[router(mode = "solidity")]
+impl<SDK: SharedAPI> SecureNotesAPI for SecureNotes<SDK> {
+    #[function_id("registerUser(bytes)")]
+    fn register_user(&mut self, encryption_key: Bytes) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        // Set the encryption key if provided; mid-rotation the next key version is already taken
+        if !encryption_key.is_empty() {
+            if !PendingEncryptionKeys::get(&self.sdk, caller).is_empty() {
+                panic!("Key rotation in progress");
+            }
+            set_encryption_key(&mut self.sdk, &caller, encryption_key);
+        }
+        
+        // Calling again only replaces the key; UserRegistered fires on the first registration
+        self.ensure_registered(caller);
+    }
+    
+    #[function_id("isRegistered(address)")]
+    fn is_registered(&self, user: Address) -> bool {
+        RegisteredUsers::get(&self.sdk, user)
+    }
+    
+    #[function_id("createNote(string,string)")]
+    fn create_note(&mut self, title: String, content: String) -> U256 {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        validate_note_input(&title, &content);
+        validate_title_chars(&self.sdk, &caller, &title);
+        
+        check_note_capacity(&self.sdk, &caller, 1);
+        
+        // Register on first use
+        self.ensure_registered(caller);
+        
+        // Take the next global id
+        let note_id = allocate_note_id(&mut self.sdk, &caller);
+        
+        self.write_new_note(caller, note_id, title, content);
+        
+        note_id
+    }
+    
+    #[function_id("importEncryptedNote(string,bytes)")]
+    fn import_encrypted_note(&mut self, title: String, encrypted_content: Bytes) -> U256 {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        // Prefix with the caller so the stored blob has the usual owner || nonce || ciphertext || tag layout
+        let mut data = caller.to_vec();
+        data.extend_from_slice(&encrypted_content);
+        let data = Bytes::from(data);
+        
+        // Only accept ciphertext that opens, so the note is readable and its limits can be checked
+        let key_version = active_key_version(&self.sdk, &caller);
+        let content = match open_ciphertext(&self.sdk, &caller, key_version, cipher_mode_of(&self.sdk, &caller), &data) {
+            Ok(content) => content,
+            Err(_) => panic!("Imported content does not decrypt under your key"),
+        };
+        validate_note_input(&title, &content);
+        validate_title_chars(&self.sdk, &caller, &title);
+        
+        check_note_capacity(&self.sdk, &caller, 1);
+        
+        // Register on first use
+        self.ensure_registered(caller);
+        
+        let note_id = allocate_note_id(&mut self.sdk, &caller);
+        
+        // Stored notes are sealed under a content key of their own, so the import is re-sealed
+        let encrypted_content = seal_note_content(&mut self.sdk, &caller, &note_id, content.as_bytes());
+        self.store_new_note(caller, note_id, title, &content, encrypted_content);
+        
+        note_id
+    }
+    
+    #[function_id("createNoteTyped(string,string,uint8)")]
+    fn create_note_typed(&mut self, title: String, content: String, content_type: u8) -> U256 {
+        if content_type > CONTENT_TYPE_JSON {
+            panic!("Unknown content type");
+        }
+        
+        let note_id = self.create_note(title, content);
+        NoteContentType::set(&mut self.sdk, note_id, U256::from(content_type));
+        
+        note_id
+    }
+    
+    #[function_id("getNoteContentType(uint256)")]
+    fn get_note_content_type(&self, note_id: U256) -> u8 {
+        let caller = self.sdk.context().contract_caller();
+        if load_note_for_reader(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        NoteContentType::get(&self.sdk, note_id).to::<u8>()
+    }
+    
+    #[function_id("setNoteType(uint256,uint8)")]
+    fn set_note_type(&mut self, note_id: U256, content_type: u8) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        if content_type > CONTENT_TYPE_JSON {
+            panic!("Unknown content type");
+        }
+        NoteContentType::set(&mut self.sdk, note_id, U256::from(content_type));
+    }
+    
+    #[function_id("createNoteWithExpiry(string,string,uint256)")]
+    fn create_note_with_expiry(&mut self, title: String, content: String, expires_at: U256) -> U256 {
+        if expires_at <= U256::from(self.sdk.context().block_timestamp()) {
+            panic!("Expiry must be in the future");
+        }
+        
+        let note_id = self.create_note(title, content);
+        NoteExpiresAt::set(&mut self.sdk, note_id, expires_at);
+        
+        note_id
+    }
+    
+    #[function_id("isExpired(uint256)")]
+    fn is_expired(&self, note_id: U256) -> bool {
+        is_note_expired(&self.sdk, &note_id)
+    }
+    
+    #[function_id("setNoteExpiry(uint256,uint256)")]
+    fn set_note_expiry(&mut self, note_id: U256, expires_at: U256) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        // An expiry would make a locked note disappear
+        if NoteLocked::get(&self.sdk, note_id) {
+            panic!("Note is locked");
+        }
+        if expires_at != U256::from(0) && expires_at <= U256::from(self.sdk.context().block_timestamp()) {
+            panic!("Expiry must be in the future");
+        }
+        NoteExpiresAt::set(&mut self.sdk, note_id, expires_at);
+    }
+    
+    #[function_id("createNoteSigned(address,string,string,uint256,bytes)")]
+    fn create_note_signed(&mut self, author: Address, title: String, content: String, nonce: U256, signature: Bytes) -> U256 {
+        self.when_not_paused();
+        
+        // Bind the signature to this contract, chain and the author's next nonce so it can't be replayed
+        let digest = self.hash_create_note(author, title.clone(), content.clone(), nonce);
+        match recover_signer(&digest, &signature) {
+            Some(signer) if signer == author && author != Address::default() => {}
+            _ => panic!("Invalid signature"),
+        }
+        if nonce != UserSignedNoteNonce::get(&self.sdk, author) {
+            panic!("Invalid nonce");
+        }
+        UserSignedNoteNonce::set(&mut self.sdk, author, nonce + U256::from(1));
+        
+        validate_note_input(&title, &content);
+        validate_title_chars(&self.sdk, &author, &title);
+        
+        check_note_capacity(&self.sdk, &author, 1);
+        
+        // Register the author, not the relayer
+        self.ensure_registered(author);
+        
+        let note_id = allocate_note_id(&mut self.sdk, &author);
+        
+        self.write_new_note(author, note_id, title, content);
+        
+        note_id
+    }
+    
+    #[function_id("getSignedNoteNonce(address)")]
+    fn get_signed_note_nonce(&self, author: Address) -> U256 {
+        UserSignedNoteNonce::get(&self.sdk, author)
+    }
+    
+    #[function_id("domainSeparator()")]
+    fn domain_separator(&self) -> B256 {
+        let context = self.sdk.context();
+        eip712_domain_separator(context.block_chain_id(), &context.contract_address())
+    }
+    
+    #[function_id("hashCreateNote(address,string,string,uint256)")]
+    fn hash_create_note(&self, author: Address, title: String, content: String, nonce: U256) -> B256 {
+        let context = self.sdk.context();
+        signed_note_digest(context.block_chain_id(), &context.contract_address(), &author, &title, &content, nonce)
+    }
+    
+    #[function_id("setCreateDelegate(address,bool)")]
+    fn set_create_delegate(&mut self, delegate: Address, allowed: bool) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        CreateDelegates::set(&mut self.sdk, caller, delegate, allowed);
+    }
+    
+    #[function_id("isCreateDelegate(address,address)")]
+    fn is_create_delegate(&self, owner: Address, delegate: Address) -> bool {
+        CreateDelegates::get(&self.sdk, owner, delegate)
+    }
+    
+    #[function_id("createNoteFor(address,string,string)")]
+    fn create_note_for(&mut self, owner: Address, title: String, content: String) -> U256 {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        if !CreateDelegates::get(&self.sdk, owner, caller) {
+            panic!("Not an approved delegate");
+        }
+        
+        validate_note_input(&title, &content);
+        validate_title_chars(&self.sdk, &owner, &title);
+        
+        check_note_capacity(&self.sdk, &owner, 1);
+        
+        // Same as createNoteSigned: the owner is registered and owns the note, not the delegate
+        self.ensure_registered(owner);
+        
+        let note_id = allocate_note_id(&mut self.sdk, &owner);
+        
+        self.write_new_note(owner, note_id, title, content);
+        
+        note_id
+    }
+    
+    #[function_id("createNotesBatch(string[],string[])")]
+    fn create_notes_batch(&mut self, titles: Vec<String>, contents: Vec<String>) -> Vec<U256> {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if titles.len() != contents.len() {
+            panic!("Titles and contents length mismatch");
+        }
+        if titles.len() > MAX_BATCH_SIZE {
+            panic!("Batch exceeds maximum size");
+        }
+        for (title, content) in titles.iter().zip(contents.iter()) {
+            validate_note_input(title, content);
+            validate_title_chars(&self.sdk, &caller, title);
+        }
+        check_note_capacity(&self.sdk, &caller, titles.len());
+        
+        // Register on first use
+        self.ensure_registered(caller);
+        
+        // Notes get consecutive global ids in input order
+        let mut ids = Vec::with_capacity(titles.len());
+        for (title, content) in titles.into_iter().zip(contents.into_iter()) {
+            let note_id = allocate_note_id(&mut self.sdk, &caller);
+            self.write_new_note(caller, note_id, title, content);
+            ids.push(note_id);
+        }
+        
+        ids
+    }
+    
+    #[function_id("getNote(uint256)")]
+    fn get_note(&self, note_id: U256) -> (String, String, U256, U256) {
+        let caller = self.sdk.context().contract_caller();
+        
+        let (exists, title, content, created_at, updated_at) = self.try_get_note(note_id);
+        if exists {
+            return (title, content, created_at, updated_at);
+        }
+        
+        // Tell a missing (or binned) note apart from someone else's note
+        let owner = NoteOwner::get(&self.sdk, note_id);
+        if owner == Address::default() || owner == caller {
+            panic!("Note does not exist");
+        }
+        panic!("Not authorized to read this note");
+    }
+    
+    #[function_id("getNoteStrict(uint256)")]
+    fn get_note_strict(&self, note_id: U256) -> (String, String, U256, U256) {
+        let caller = self.sdk.context().contract_caller();
+        
+        let owner = NoteOwner::get(&self.sdk, note_id);
+        let note = match load_stored_note(&self.sdk, &owner, &note_id) {
+            Some(note) if owner != Address::default() => note,
+            _ => panic!("Note does not exist"),
+        };
+        if owner != caller && !NoteSharedWith::get(&self.sdk, note_id, caller) && !NotePublic::get(&self.sdk, note_id) {
+            panic!("Not authorized to read this note");
+        }
+        if NoteDeleted::get(&self.sdk, note_id) {
+            panic!("Note is deleted");
+        }
+        if is_note_expired(&self.sdk, &note_id) {
+            panic!("Note has expired");
+        }
+        
+        match decrypt_stored_note(&self.sdk, &caller, &note_id) {
+            Ok(content) => (note.title, content, note.created_at, note.updated_at),
+            Err(_) => panic!("Note content could not be decrypted"),
+        }
+    }
+    
+    #[function_id("readNoteLogged(uint256)")]
+    fn read_note_logged(&mut self, note_id: U256) -> (String, String, U256) {
+        // It writes a log, so it's blocked with the other transactions
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        let (title, content, _, updated_at) = self.get_note(note_id);
+        
+        if NoteOwner::get(&self.sdk, note_id) != caller {
+            let note_id_topic = fluentbase_sdk::B256::from(note_id.to_be_bytes::<32>());
+            emit_event(&mut self.sdk, EVENT_NOTE_ACCESSED, Bytes::new(), &[address_topic(&caller), note_id_topic]);
+        }
+        
+        (title, content, updated_at)
+    }
+    
+    #[function_id("tryGetNote(uint256)")]
+    fn try_get_note(&self, note_id: U256) -> (bool, String, String, U256, U256) {
+        let caller = self.sdk.context().contract_caller();
+        
+        // Owners and shared readers both decrypt with the owner's key
+        if let Some(note) = load_note_for_reader(&self.sdk, &caller, &note_id) {
+            // Decrypt content
+            let decrypted_content = match decrypt_stored_note(&self.sdk, &caller, &note_id) {
+                Ok(content) => content,
+                Err(e) => String::from(e.message()),
+            };
+            
+            (true, note.title.clone(), decrypted_content, note.created_at, note.updated_at)
+        } else {
+            (false, String::new(), String::new(), U256::from(0), U256::from(0))
+        }
+    }
+    
+    #[function_id("getNoteTimestamps(uint256)")]
+    fn get_note_timestamps(&self, note_id: U256) -> (U256, U256) {
+        let caller = self.sdk.context().contract_caller();
+        
+        // Returns (created_at, updated_at); creation time is never touched by updates
+        if let Some(note) = load_note(&self.sdk, &caller, &note_id) {
+            (note.created_at, note.updated_at)
+        } else {
+            (U256::from(0), U256::from(0))
+        }
+    }
+    
+    #[function_id("getNoteBlock(uint256)")]
+    fn get_note_block(&self, note_id: U256) -> U256 {
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            return U256::from(0);
+        }
+        NoteCreatedBlock::get(&self.sdk, note_id)
+    }
+    
+    #[function_id("getNoteOwner(uint256)")]
+    fn get_note_owner(&self, note_id: U256) -> Address {
+        NoteOwner::get(&self.sdk, note_id)
+    }
+    
+    #[function_id("getNoteMeta(uint256)")]
+    fn get_note_meta(&self, note_id: U256) -> (Address, Address, U256, u8) {
+        let caller = self.sdk.context().contract_caller();
+        match load_note_for_reader(&self.sdk, &caller, &note_id) {
+            Some(note) => {
+                let content_type = NoteContentType::get(&self.sdk, note_id).to::<u8>();
+                (note.owner, NoteLastEditor::get(&self.sdk, note_id), note.updated_at, content_type)
+            }
+            None => panic!("Note does not exist"),
+        }
+    }
+    
+    #[function_id("noteExists(address,uint256)")]
+    fn note_exists(&self, owner: Address, note_id: U256) -> bool {
+        is_visible_note_of(&self.sdk, &owner, &note_id)
+    }
+    
+    #[function_id("getNoteStats(uint256)")]
+    fn get_note_stats(&self, note_id: U256) -> (U256, U256) {
+        // Same access rules (and reverts) as get_note
+        let (_, content, _, _) = self.get_note(note_id);
+        
+        // Words are runs of non-whitespace separated by ASCII whitespace
+        let char_count = content.chars().count();
+        let word_count = content.split_ascii_whitespace().count();
+        (U256::from(char_count), U256::from(word_count))
+    }
+    
+    #[function_id("getNoteEnvelope(uint256)")]
+    fn get_note_envelope(&self, note_id: U256) -> Bytes {
+        let caller = self.sdk.context().contract_caller();
+        if load_note_for_reader(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        NoteEnvelope::get(&self.sdk, note_id, caller)
+    }
+    
+    #[function_id("getNoteCiphertext(uint256)")]
+    fn get_note_ciphertext(&self, note_id: U256) -> Bytes {
+        let caller = self.sdk.context().contract_caller();
+        
+        if let Some(note) = load_note_for_reader(&self.sdk, &caller, &note_id) {
+            return note.encrypted_content;
+        }
+        
+        // Same distinction getNote makes
+        let owner = NoteOwner::get(&self.sdk, note_id);
+        if owner == Address::default() || owner == caller {
+            panic!("Note does not exist");
+        }
+        panic!("Not authorized to read this note");
+    }
+    
+    #[function_id("setNoteAttachment(uint256,bytes)")]
+    fn set_note_attachment(&mut self, note_id: U256, data: Bytes) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        if NoteLocked::get(&self.sdk, note_id) {
+            panic!("Note is locked");
+        }
+        if data.len() > MAX_ATTACHMENT_LEN {
+            panic!("Attachment too large");
+        }
+        
+        store_attachment(&mut self.sdk, &caller, &note_id, &data);
+    }
+    
+    #[function_id("getNoteAttachment(uint256)")]
+    fn get_note_attachment(&self, note_id: U256) -> Bytes {
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        match load_attachment(&self.sdk, &caller, &note_id) {
+            Ok(attachment) => Bytes::from(attachment),
+            Err(_) => panic!("Attachment could not be decrypted"),
+        }
+    }
+    
+    #[function_id("setNoteAttachmentCid(uint256,bytes)")]
+    fn set_note_attachment_cid(&mut self, note_id: U256, cid: Bytes) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        if NoteLocked::get(&self.sdk, note_id) {
+            panic!("Note is locked");
+        }
+        if cid.len() > MAX_ATTACHMENT_CID_LEN {
+            panic!("Attachment CID too long");
+        }
+        
+        NoteAttachmentCid::set(&mut self.sdk, note_id, cid);
+    }
+    
+    #[function_id("getNoteAttachmentCid(uint256)")]
+    fn get_note_attachment_cid(&self, note_id: U256) -> Bytes {
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note_for_reader(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        NoteAttachmentCid::get(&self.sdk, note_id)
+    }
+    
+    #[function_id("getNoteHash(uint256)")]
+    fn get_note_hash(&self, note_id: U256) -> B256 {
+        let caller = self.sdk.context().contract_caller();
+        
+        // Owner only: a plaintext hash can be brute-forced for short notes
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        NoteContentHash::get(&self.sdk, note_id)
+    }
+    
+    #[function_id("verifyNoteIntegrity(uint256)")]
+    fn verify_note_integrity(&self, note_id: U256) -> bool {
+        let caller = self.sdk.context().contract_caller();
+        let note = match load_note(&self.sdk, &caller, &note_id) {
+            Some(note) => note,
+            None => panic!("Note does not exist"),
+        };
+        
+        // Ciphertext that no longer opens counts as corrupted too
+        match open_with_envelope(&self.sdk, &caller, &note) {
+            Ok(plaintext) => keccak256(plaintext.as_bytes()) == NoteContentHash::get(&self.sdk, note_id),
+            Err(_) => false,
+        }
+    }
+    
+    #[function_id("updateNote(uint256,string,string)")]
+    fn update_note(&mut self, note_id: U256, title: String, content: String) -> U256 {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        validate_note_input(&title, &content);
+        validate_title_chars(&self.sdk, &caller, &title);
+        
+        // Register on first use
+        self.ensure_registered(caller);
+        
+        let mut note = self.load_editable_note(caller, note_id);
+        let owner = note.owner;
+        if NoteLocked::get(&self.sdk, note_id) {
+            panic!("Note is locked");
+        }
+        
+        let content_len = content.len();
+        let content_hash = keccak256(content.as_bytes());
+        
+        // Keep the content being replaced in the note's history, while its key still opens it
+        push_note_version(&mut self.sdk, &note);
+        
+        // Seal the content under a fresh key wrapped for the owner, whoever edits it
+        let encrypted_content = seal_note_content(&mut self.sdk, &owner, &note_id, content.as_bytes());
+        NoteContentHash::set(&mut self.sdk, note_id, content_hash);
+        
+        // Re-point the title index from the old title to the new one
+        unindex_title(&mut self.sdk, &owner, &note.title, note_id);
+        index_title(&mut self.sdk, &owner, &title, note_id);
+        
+        // Update the note
+        let previous_title = core::mem::replace(&mut note.title, title);
+        note.encrypted_content = encrypted_content;
+        note.key_version = active_key_version(&self.sdk, &owner);
+        note.cipher_mode = cipher_mode_of(&self.sdk, &owner);
+        note.updated_at = U256::from(self.sdk.context().block_timestamp());
+        
+        // Save updated note
+        store_note(&mut self.sdk, &owner, &note_id, &note);
+        NoteLastEditor::set(&mut self.sdk, note_id, caller);
+        
+        let note_id_bytes = note_id.to_be_bytes::<32>();
+        let note_id_topic = fluentbase_sdk::B256::from(note_id_bytes);
+        
+        let content_hash_topic = keccak256(&note.encrypted_content);
+        
+        let event_data = encode_note_updated_data(&note.title, content_len, note.updated_at, &previous_title);
+        
+        // Emit event
+        emit_event(
+            &mut self.sdk,
+            EVENT_NOTE_UPDATED,
+            event_data,
+            &[address_topic(&owner), note_id_topic, content_hash_topic],
+        );
+        
+        note_id
+    }
+    
+    #[function_id("updateNoteTitle(uint256,string)")]
+    fn update_note_title(&mut self, note_id: U256, title: String) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        validate_note_input(&title, "");
+        validate_title_chars(&self.sdk, &caller, &title);
+        
+        // Register on first use
+        self.ensure_registered(caller);
+        
+        // Same editors as updateNote: the owner and the grantees allowed to edit
+        let note = self.load_editable_note(caller, note_id);
+        let owner = note.owner;
+        if NoteLocked::get(&self.sdk, note_id) {
+            panic!("Note is locked");
+        }
+        
+        unindex_title(&mut self.sdk, &owner, &note.title, note_id);
+        index_title(&mut self.sdk, &owner, &title, note_id);
+        
+        // Only the title and timestamp are written, the content chunks stay as they are
+        let updated_at = U256::from(self.sdk.context().block_timestamp());
+        NoteTitle::set(&mut self.sdk, note_id, title.clone());
+        NoteUpdatedAt::set(&mut self.sdk, note_id, updated_at);
+        NoteLastEditor::set(&mut self.sdk, note_id, caller);
+        
+        let note_id_topic = fluentbase_sdk::B256::from(note_id.to_be_bytes::<32>());
+        let content_hash_topic = keccak256(&note.encrypted_content);
+        
+        // Both AEADs keep the plaintext length, so it can be read off the ciphertext
+        let content_len = note.encrypted_content.len().saturating_sub(OWNER_PREFIX_LEN + NONCE_LEN + TAG_LEN);
+        let event_data = encode_note_updated_data(&title, content_len, updated_at, &note.title);
+        
+        emit_event(
+            &mut self.sdk,
+            EVENT_NOTE_UPDATED,
+            event_data,
+            &[address_topic(&owner), note_id_topic, content_hash_topic],
+        );
+    }
+    
+    #[function_id("updateNoteContent(uint256,string)")]
+    fn update_note_content(&mut self, note_id: U256, content: String) {
+        let title = NoteTitle::get(&self.sdk, note_id);
+        self.update_note(note_id, title, content);
+    }
+    
+    #[function_id("appendToNote(uint256,string)")]
+    fn append_to_note(&mut self, note_id: U256, extra: String) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        // Same editors as updateNote
+        let note = self.load_editable_note(caller, note_id);
+        
+        // Appending to content that doesn't open would silently drop it, so revert instead
+        let mut content = match open_with_envelope(&self.sdk, &note.owner, &note) {
+            Ok(content) => content,
+            Err(_) => panic!("Existing content could not be decrypted"),
+        };
+        content.push_str(&extra);
+        
+        self.update_note(note_id, note.title, content);
+    }
+    
+    #[function_id("getNoteVersionCount(uint256)")]
+    fn get_note_version_count(&self, note_id: U256) -> U256 {
+        let caller = self.sdk.context().contract_caller();
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        NoteVersionCount::get(&self.sdk, note_id)
+    }
+    
+    #[function_id("getNoteVersion(uint256,uint256)")]
+    fn get_note_version(&self, note_id: U256, version: U256) -> String {
+        let caller = self.sdk.context().contract_caller();
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        if version >= NoteVersionCount::get(&self.sdk, note_id) {
+            panic!("Version does not exist");
+        }
+        
+        // Each version decrypts under the key it was written with
+        let key_version = NoteVersionKeyId::get(&self.sdk, note_id, version);
+        let cipher_mode = NoteVersionCipherMode::get(&self.sdk, note_id, version).to::<u8>();
+        let content = NoteVersionContent::get(&self.sdk, note_id, version);
+        decrypt_as_owner(&self.sdk, &caller, key_version, cipher_mode, &content)
+    }
+    
+    #[function_id("revertNoteToVersion(uint256,uint256)")]
+    fn revert_note_to_version(&mut self, note_id: U256, version: U256) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        let note = match load_note(&self.sdk, &caller, &note_id) {
+            Some(note) => note,
+            None => panic!("Note does not exist"),
+        };
+        if version >= NoteVersionCount::get(&self.sdk, note_id) {
+            panic!("Version does not exist");
+        }
+        
+        // getNoteVersion reports failures in place of the content, which must not be written back
+        let key_version = NoteVersionKeyId::get(&self.sdk, note_id, version);
+        let cipher_mode = NoteVersionCipherMode::get(&self.sdk, note_id, version).to::<u8>();
+        let encrypted = NoteVersionContent::get(&self.sdk, note_id, version);
+        let content = match open_ciphertext(&self.sdk, &caller, key_version, cipher_mode, &encrypted) {
+            Ok(content) => content,
+            Err(_) => panic!("Version could not be decrypted"),
+        };
+        
+        // Goes through a regular update so the content is re-encrypted under the active key
+        self.update_note(note_id, note.title, content);
+    }
+    
+    #[function_id("deleteNote(uint256)")]
+    fn delete_note(&mut self, note_id: U256) -> U256 {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        if NoteLocked::get(&self.sdk, note_id) {
+            panic!("Note is locked");
+        }
+        
+        self.bin_note(caller, note_id);
+        note_id
+    }
+    
+    #[function_id("deleteNotesBatch(uint256[])")]
+    fn delete_notes_batch(&mut self, note_ids: Vec<U256>) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if note_ids.len() > MAX_BATCH_SIZE {
+            panic!("Batch exceeds maximum size");
+        }
+        
+        // Deleting only flags notes, so ids stay valid in any order; missing, foreign, locked
+        // and already binned ids (including repeats) are skipped
+        for note_id in note_ids {
+            if load_note(&self.sdk, &caller, &note_id).is_some() && !NoteLocked::get(&self.sdk, note_id) {
+                self.bin_note(caller, note_id);
+            }
+        }
+    }
+    
+    #[function_id("restoreNote(uint256)")]
+    fn restore_note(&mut self, note_id: U256) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_stored_note(&self.sdk, &caller, &note_id).is_none() || !NoteDeleted::get(&self.sdk, note_id) {
+            panic!("Note is not in the recycle bin");
+        }
+        
+        NoteDeleted::set(&mut self.sdk, note_id, false);
+        
+        let total = TotalNotes::get(&self.sdk);
+        TotalNotes::set(&mut self.sdk, total + U256::from(1));
+    }
+    
+    #[function_id("getDeletedNotes()")]
+    fn get_deleted_notes(&self) -> (Vec<U256>, Vec<String>, Vec<U256>, Vec<U256>) {
+        let caller = self.sdk.context().contract_caller();
+        let notes = get_deleted_notes_for(&self.sdk, &caller);
+        
+        notes_to_columns(&notes)
+    }
+    
+    #[function_id("purgeNote(uint256)")]
+    fn purge_note(&mut self, note_id: U256) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        // Reordering the note list mid-rotation could let a note slip past the cursor
+        if !PendingEncryptionKeys::get(&self.sdk, caller).is_empty() {
+            panic!("Key rotation in progress");
+        }
+        
+        // Check the note exists and belongs to the caller
+        let deleted_note = match load_stored_note(&self.sdk, &caller, &note_id) {
+            Some(note) => note,
+            None => panic!("Note does not exist"),
+        };
+        if NoteLocked::get(&self.sdk, note_id) {
+            panic!("Note is locked");
+        }
+        remove_note(&mut self.sdk, &caller, &deleted_note);
+        
+        self.emit_note_deleted(caller, note_id);
+    }
+    
+    #[function_id("deleteAllNotes()")]
+    fn delete_all_notes(&mut self) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        // Same restriction as purgeNote
+        if !PendingEncryptionKeys::get(&self.sdk, caller).is_empty() {
+            panic!("Key rotation in progress");
+        }
+        
+        // Walk from the end so every note moved into a gap was already looked at, and the
+        // list stays consistent if a call runs out of gas part way through
+        let count = UserNotesCount::get(&self.sdk, caller);
+        let max_notes = U256::from(MAX_NOTES_PER_USER);
+        let stop = if count > max_notes { count - max_notes } else { U256::from(0) };
+        
+        let mut cleared = U256::from(0);
+        let mut index = count;
+        while index > stop {
+            index -= U256::from(1);
+            let note_id = UserNoteIds::get(&self.sdk, caller, index);
+            if NoteLocked::get(&self.sdk, note_id) {
+                continue;
+            }
+            if let Some(note) = load_stored_note(&self.sdk, &caller, &note_id) {
+                remove_note(&mut self.sdk, &caller, &note);
+                cleared += U256::from(1);
+            }
+        }
+        
+        let cleared_data = Bytes::from(cleared.to_be_bytes::<32>().to_vec());
+        emit_event(&mut self.sdk, EVENT_ALL_NOTES_DELETED, cleared_data, &[address_topic(&caller)]);
+    }
+    
+    #[function_id("purgeExpired(uint256,uint256)")]
+    fn purge_expired(&mut self, offset: U256, limit: U256) -> U256 {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        // Same restriction as purgeNote
+        if !PendingEncryptionKeys::get(&self.sdk, caller).is_empty() {
+            panic!("Key rotation in progress");
+        }
+        
+        let count = UserNotesCount::get(&self.sdk, caller);
+        if offset >= count {
+            return U256::from(0);
+        }
+        let max_page = U256::from(MAX_PAGE_SIZE);
+        let limit = if limit > max_page { max_page } else { limit };
+        let end = if count - offset < limit { count } else { offset + limit };
+        
+        // Walk the range backwards so a removal only moves an entry into a position
+        // that has already been checked
+        let mut purged = U256::from(0);
+        for index in (to_loop_bound(offset)..to_loop_bound(end)).rev() {
+            let note_id = UserNoteIds::get(&self.sdk, caller, U256::from(index));
+            if !is_note_expired(&self.sdk, &note_id) || NoteLocked::get(&self.sdk, note_id) {
+                continue;
+            }
+            if let Some(note) = load_stored_note(&self.sdk, &caller, &note_id) {
+                remove_note(&mut self.sdk, &caller, &note);
+                purged += U256::from(1);
+                
+                self.emit_note_deleted(caller, note_id);
+            }
+        }
+        
+        purged
+    }
+    
+    #[function_id("archiveNote(uint256)")]
+    fn archive_note(&mut self, note_id: U256) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Only the note owner can archive it");
+        }
+        if !NoteArchived::get(&self.sdk, note_id) {
+            self.set_archived(caller, note_id, true);
+        }
+    }
+    
+    #[function_id("unarchiveNote(uint256)")]
+    fn unarchive_note(&mut self, note_id: U256) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Only the note owner can unarchive it");
+        }
+        if NoteArchived::get(&self.sdk, note_id) {
+            self.set_archived(caller, note_id, false);
+        }
+    }
+    
+    #[function_id("getArchivedNotes()")]
+    fn get_archived_notes(&self) -> (Vec<U256>, Vec<String>, Vec<U256>) {
+        let caller = self.sdk.context().contract_caller();
+        
+        let mut ids = Vec::new();
+        let mut titles = Vec::new();
+        let mut timestamps = Vec::new();
+        for note_id in load_note_ids(&self.sdk, &caller) {
+            if NoteArchived::get(&self.sdk, note_id) {
+                if let Some(note) = load_note(&self.sdk, &caller, &note_id) {
+                    ids.push(note.id);
+                    titles.push(note.title);
+                    timestamps.push(note.updated_at);
+                }
+            }
+        }
+        
+        (ids, titles, timestamps)
+    }
+    
+    #[function_id("findNoteByTitle(string)")]
+    fn find_note_by_title(&self, title: String) -> (bool, U256) {
+        let caller = self.sdk.context().contract_caller();
+        
+        let entry = TitleToNoteId::get(&self.sdk, caller, keccak256(title.as_bytes()));
+        if entry == U256::from(0) {
+            return (false, U256::from(0));
+        }
+        
+        // Double-check against the note itself so binned notes aren't reported
+        let note_id = entry - U256::from(1);
+        match load_note(&self.sdk, &caller, &note_id) {
+            Some(note) if note.title == title => (true, note_id),
+            _ => (false, U256::from(0)),
+        }
+    }
+    
+    #[function_id("setNoteTags(uint256,string[])")]
+    fn set_note_tags(&mut self, note_id: U256, tags: Vec<String>) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        if tags.len() > MAX_TAGS_PER_NOTE {
+            panic!("Too many tags");
+        }
+        if tags.iter().any(|tag| tag.len() > MAX_TAG_LEN) {
+            panic!("Tag exceeds maximum length");
+        }
+        
+        // No event for tag changes to keep this cheap
+        store_tags(&mut self.sdk, &note_id, &tags);
+    }
+    
+    #[function_id("getNoteTags(uint256)")]
+    fn get_note_tags(&self, note_id: U256) -> Vec<String> {
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note_for_reader(&self.sdk, &caller, &note_id).is_none() {
+            return Vec::new();
+        }
+        load_tags(&self.sdk, &note_id)
+    }
+    
+    #[function_id("getNotesByTag(string)")]
+    fn get_notes_by_tag(&self, tag: String) -> Vec<U256> {
+        let caller = self.sdk.context().contract_caller();
+        
+        let mut ids = Vec::new();
+        for note_id in load_note_ids(&self.sdk, &caller) {
+            if is_tagged_note_of(&self.sdk, &caller, &note_id, &tag) {
+                ids.push(note_id);
+            }
+        }
+        
+        ids
+    }
+    
+    #[function_id("countNotesByTag(string)")]
+    fn count_notes_by_tag(&self, tag: String) -> U256 {
+        let caller = self.sdk.context().contract_caller();
+        
+        let mut count = U256::from(0);
+        for note_id in load_note_ids(&self.sdk, &caller) {
+            if is_tagged_note_of(&self.sdk, &caller, &note_id, &tag) {
+                count += U256::from(1);
+            }
+        }
+        
+        count
+    }
+    
+    #[function_id("linkNotes(uint256,uint256)")]
+    fn link_notes(&mut self, from_id: U256, to_id: U256) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &from_id).is_none() || load_note(&self.sdk, &caller, &to_id).is_none() {
+            panic!("Note does not exist");
+        }
+        if from_id == to_id {
+            panic!("Cannot link a note to itself");
+        }
+        
+        let mut links = load_links(&self.sdk, &from_id);
+        if links.contains(&to_id) {
+            return;
+        }
+        if links.len() >= MAX_LINKS_PER_NOTE {
+            panic!("Too many links");
+        }
+        links.push(to_id);
+        store_links(&mut self.sdk, &from_id, &links);
+    }
+    
+    #[function_id("unlinkNotes(uint256,uint256)")]
+    fn unlink_notes(&mut self, from_id: U256, to_id: U256) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &from_id).is_none() {
+            panic!("Note does not exist");
+        }
+        
+        let mut links = load_links(&self.sdk, &from_id);
+        links.retain(|link| *link != to_id);
+        store_links(&mut self.sdk, &from_id, &links);
+    }
+    
+    #[function_id("getNoteLinks(uint256)")]
+    fn get_note_links(&self, note_id: U256) -> Vec<U256> {
+        let caller = self.sdk.context().contract_caller();
+        
+        let note = match load_note_for_reader(&self.sdk, &caller, &note_id) {
+            Some(note) => note,
+            None => return Vec::new(),
+        };
+        load_links(&self.sdk, &note_id)
+            .into_iter()
+            .filter(|link| load_note(&self.sdk, &note.owner, link).is_some())
+            .collect()
+    }
+    
+    #[function_id("setNoteColor(uint256,uint256)")]
+    fn set_note_color(&mut self, note_id: U256, color: U256) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        if color > U256::from(0xFFFFFFu64) {
+            panic!("Invalid color");
+        }
+        NoteColor::set(&mut self.sdk, note_id, color);
+    }
+    
+    #[function_id("setNoteCategory(uint256,uint256)")]
+    fn set_note_category(&mut self, note_id: U256, category: U256) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        
+        // Reassigning the same category is a no-op, so folder views never see a spurious event
+        if NoteCategory::get(&self.sdk, note_id) == category {
+            return;
+        }
+        
+        NoteCategory::set(&mut self.sdk, note_id, category);
+        
+        let note_id_topic = fluentbase_sdk::B256::from(note_id.to_be_bytes::<32>());
+        let category_data = Bytes::from(category.to_be_bytes::<32>().to_vec());
+        emit_event(&mut self.sdk, EVENT_NOTE_CATEGORIZED, category_data, &[address_topic(&caller), note_id_topic]);
+    }
+    
+    #[function_id("getNotesByCategory(uint256)")]
+    fn get_notes_by_category(&self, category: U256) -> (Vec<U256>, Vec<String>) {
+        let caller = self.sdk.context().contract_caller();
+        
+        let mut ids = Vec::new();
+        let mut titles = Vec::new();
+        for note_id in load_note_ids(&self.sdk, &caller) {
+            if NoteCategory::get(&self.sdk, note_id) == category {
+                if let Some(note) = load_note(&self.sdk, &caller, &note_id) {
+                    ids.push(note.id);
+                    titles.push(note.title);
+                }
+            }
+        }
+        
+        (ids, titles)
+    }
+    
+    #[function_id("pinNote(uint256)")]
+    fn pin_note(&mut self, note_id: U256) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        NotePinned::set(&mut self.sdk, note_id, true);
+    }
+    
+    #[function_id("unpinNote(uint256)")]
+    fn unpin_note(&mut self, note_id: U256) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        NotePinned::set(&mut self.sdk, note_id, false);
+    }
+    
+    #[function_id("getPinnedNotes()")]
+    fn get_pinned_notes(&self) -> (Vec<U256>, Vec<String>, Vec<U256>, Vec<U256>) {
+        let caller = self.sdk.context().contract_caller();
+        
+        let mut notes = Vec::new();
+        for note_id in load_note_ids(&self.sdk, &caller) {
+            if NotePinned::get(&self.sdk, note_id) {
+                if let Some(note) = load_note(&self.sdk, &caller, &note_id) {
+                    notes.push(note);
+                }
+            }
+        }
+        
+        notes_to_columns(&notes)
+    }
+    
+    #[function_id("lockNote(uint256)")]
+    fn lock_note(&mut self, note_id: U256) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Only the note owner can lock it");
+        }
+        NoteLocked::set(&mut self.sdk, note_id, true);
+    }
+    
+    #[function_id("unlockNote(uint256)")]
+    fn unlock_note(&mut self, note_id: U256) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Only the note owner can unlock it");
+        }
+        NoteLocked::set(&mut self.sdk, note_id, false);
+    }
+    
+    #[function_id("setNotesOrder(uint256[])")]
+    fn set_notes_order(&mut self, ordered_ids: Vec<U256>) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        // The new order must be a permutation of the caller's current ids
+        let mut current = load_note_ids(&self.sdk, &caller);
+        let mut requested = ordered_ids.clone();
+        current.sort();
+        requested.sort();
+        if current != requested {
+            panic!("Note order must list each of your notes exactly once");
+        }
+        
+        for (position, note_id) in ordered_ids.iter().enumerate() {
+            NoteSortOrder::set(&mut self.sdk, *note_id, U256::from(position));
+        }
+    }
+    
+    #[function_id("reorderNote(uint256,uint256)")]
+    fn reorder_note(&mut self, note_id: U256, new_index: U256) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        
+        // Every position is rewritten, so keep it bounded like getNotesSorted
+        let count = UserNotesCount::get(&self.sdk, caller);
+        if count > U256::from(MAX_NOTES_PER_USER) {
+            panic!("Too many notes to reorder, use setNotesOrder");
+        }
+        if new_index >= count {
+            panic!("Index out of range");
+        }
+        
+        let mut ordered_ids = load_note_ids(&self.sdk, &caller);
+        ordered_ids.sort_by_key(|id| NoteSortOrder::get(&self.sdk, *id));
+        ordered_ids.retain(|id| *id != note_id);
+        ordered_ids.insert(to_loop_bound(new_index), note_id);
+        
+        for (position, id) in ordered_ids.iter().enumerate() {
+            NoteSortOrder::set(&mut self.sdk, *id, U256::from(position));
+        }
+    }
+    
+    #[function_id("transferNote(uint256,address)")]
+    fn transfer_note(&mut self, note_id: U256, to: Address) -> U256 {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if to == Address::default() || to == caller {
+            panic!("Invalid transfer recipient");
+        }
+        // The note is re-encrypted under the recipient's key; without one it would fall back to
+        // the key anyone can derive from their address
+        if UserEncryptionKeys::get(&self.sdk, to).is_empty() {
+            panic!("Transfer recipient has no encryption key");
+        }
+        if !PendingEncryptionKeys::get(&self.sdk, caller).is_empty() {
+            panic!("Key rotation in progress");
+        }
+        check_note_capacity(&self.sdk, &to, 1);
+        let note = match load_note(&self.sdk, &caller, &note_id) {
+            Some(note) => note,
+            None => panic!("Only the note owner can transfer it"),
+        };
+        if NoteLocked::get(&self.sdk, note_id) {
+            panic!("Note is locked");
+        }
+        
+        // Decrypt under the sender's key before the note leaves their set
+        let plaintext = match open_with_envelope(&self.sdk, &caller, &note) {
+            Ok(plaintext) => plaintext,
+            Err(_) => panic!("Failed to decrypt note for transfer"),
+        };
+        let attachment = match load_attachment(&self.sdk, &caller, &note_id) {
+            Ok(attachment) => attachment,
+            Err(_) => panic!("Failed to decrypt note for transfer"),
+        };
+        let tags = load_tags(&self.sdk, &note_id);
+        let content_type = NoteContentType::get(&self.sdk, note_id);
+        let expires_at = NoteExpiresAt::get(&self.sdk, note_id);
+        let color = NoteColor::get(&self.sdk, note_id);
+        let attachment_cid = NoteAttachmentCid::get(&self.sdk, note_id);
+        let created_block = NoteCreatedBlock::get(&self.sdk, note_id);
+        remove_note(&mut self.sdk, &caller, &note);
+        
+        // Append to the recipient's notes under a fresh id, re-encrypted under their key
+        let new_id = allocate_note_id(&mut self.sdk, &to);
+        let encrypted_content = seal_note_content(&mut self.sdk, &to, &new_id, plaintext.as_bytes());
+        let transferred = Note {
+            id: new_id,
+            owner: to,
+            encrypted_content,
+            created_at: note.created_at,
+            updated_at: note.updated_at,
+            title: note.title.clone(),
+            key_version: active_key_version(&self.sdk, &to),
+            cipher_mode: cipher_mode_of(&self.sdk, &to),
+        };
+        store_note(&mut self.sdk, &to, &new_id, &transferred);
+        NoteContentHash::set(&mut self.sdk, new_id, keccak256(plaintext.as_bytes()));
+        NoteLastEditor::set(&mut self.sdk, new_id, to);
+        NoteContentType::set(&mut self.sdk, new_id, content_type);
+        NoteExpiresAt::set(&mut self.sdk, new_id, expires_at);
+        NoteColor::set(&mut self.sdk, new_id, color);
+        store_attachment(&mut self.sdk, &to, &new_id, &attachment);
+        NoteAttachmentCid::set(&mut self.sdk, new_id, attachment_cid);
+        NoteCreatedBlock::set(&mut self.sdk, new_id, created_block);
+        store_tags(&mut self.sdk, &new_id, &tags);
+        index_title(&mut self.sdk, &to, &transferred.title, new_id);
+        
+        // Both ids go in the event data, the two parties are indexed
+        let mut data = Vec::with_capacity(64);
+        data.extend_from_slice(&note_id.to_be_bytes::<32>());
+        data.extend_from_slice(&new_id.to_be_bytes::<32>());
+        emit_event(
+            &mut self.sdk,
+            EVENT_NOTE_TRANSFERRED,
+            Bytes::from(data),
+            &[address_topic(&caller), address_topic(&to)],
+        );
+        
+        new_id
+    }
+    
+    #[function_id("shareNote(uint256,address)")]
+    fn share_note(&mut self, note_id: U256, with: Address) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        // Only the owner of an existing note can share it
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Only the note owner can share it");
+        }
+        if with == Address::default() || with == caller {
+            panic!("Invalid share recipient");
+        }
+        // The envelope is wrapped under the grantee's key, so they need to have set one up;
+        // a keyless one would fall back to the key anyone can derive from their address
+        if !self.has_encryption_key(with) {
+            panic!("Share recipient has no encryption key");
+        }
+        
+        let mut readers = load_shares(&self.sdk, &note_id);
+        if !readers.contains(&with) {
+            readers.push(with);
+            store_shares(&mut self.sdk, &note_id, &readers);
+            let content_key = note_content_key(&self.sdk, &note_id);
+            wrap_note_key(&mut self.sdk, &note_id, &with, &content_key);
+        }
+        
+        // Emit event with owner and grantee as indexed parameters
+        let note_id_data = Bytes::from(note_id.to_be_bytes::<32>().to_vec());
+        emit_event(
+            &mut self.sdk,
+            EVENT_NOTE_SHARED,
+            note_id_data,
+            &[address_topic(&caller), address_topic(&with)],
+        );
+    }
+    
+    #[function_id("revokeShare(uint256,address)")]
+    fn revoke_share(&mut self, note_id: U256, with: Address) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        let note = match load_note(&self.sdk, &caller, &note_id) {
+            Some(note) => note,
+            None => panic!("Only the note owner can revoke access"),
+        };
+        
+        let mut readers = load_shares(&self.sdk, &note_id);
+        if !readers.contains(&with) {
+            return;
+        }
+        readers.retain(|reader| *reader != with);
+        store_shares(&mut self.sdk, &note_id, &readers);
+        
+        // The revoked grantee may have kept the content key, so the content moves to a new one
+        if rekey_note(&mut self.sdk, &note).is_err() {
+            panic!("Note content could not be decrypted");
+        }
+    }
+    
+    #[function_id("setNoteEditor(uint256,address,bool)")]
+    fn set_note_editor(&mut self, note_id: U256, editor: Address, allowed: bool) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Only the note owner can choose editors");
+        }
+        if allowed && !NoteSharedWith::get(&self.sdk, note_id, editor) {
+            panic!("Editor must be a share recipient");
+        }
+        NoteEditors::set(&mut self.sdk, note_id, editor, allowed);
+    }
+    
+    #[function_id("isNoteEditor(uint256,address)")]
+    fn is_note_editor(&self, note_id: U256, editor: Address) -> bool {
+        NoteEditors::get(&self.sdk, note_id, editor)
+    }
+    
+    #[function_id("getSharedWithMe()")]
+    fn get_shared_with_me(&self) -> (Vec<Address>, Vec<U256>) {
+        let caller = self.sdk.context().contract_caller();
+        let count = to_loop_bound(SharedWithMeCount::get(&self.sdk, caller));
+        
+        let mut owners = Vec::new();
+        let mut ids = Vec::new();
+        for index in 0..count {
+            let note_id = SharedWithMeAt::get(&self.sdk, caller, U256::from(index));
+            if let Some(note) = load_note_for_reader(&self.sdk, &caller, &note_id) {
+                owners.push(note.owner);
+                ids.push(note_id);
+            }
+        }
+        
+        (owners, ids)
+    }
+    
+    #[function_id("setNotePublic(uint256,bool)")]
+    fn set_note_public(&mut self, note_id: U256, public: bool) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if load_note(&self.sdk, &caller, &note_id).is_none() {
+            panic!("Only the note owner can change its visibility");
+        }
+        
+        NotePublic::set(&mut self.sdk, note_id, public);
+        
+        let note_id_topic = fluentbase_sdk::B256::from(note_id.to_be_bytes::<32>());
+        let public_data = Bytes::from(U256::from(public as u8).to_be_bytes::<32>().to_vec());
+        emit_event(
+            &mut self.sdk,
+            EVENT_NOTE_VISIBILITY_CHANGED,
+            public_data,
+            &[address_topic(&caller), note_id_topic],
+        );
+    }
+    
+    #[function_id("getPublicNote(address,uint256)")]
+    fn get_public_note(&self, owner: Address, note_id: U256) -> (String, String, U256) {
+        let note = match load_note(&self.sdk, &owner, &note_id) {
+            Some(note) if NotePublic::get(&self.sdk, note_id) => note,
+            _ => panic!("Note is not public"),
+        };
+        
+        // Public notes are still stored encrypted, so decrypt with the owner's key
+        let content = match decrypt_stored_note(&self.sdk, &owner, &note_id) {
+            Ok(content) => content,
+            Err(e) => String::from(e.message()),
+        };
+        (note.title, content, note.updated_at)
+    }
+    
+    #[function_id("getNotePublic(address,uint256)")]
+    fn get_note_public(&self, owner: Address, note_id: U256) -> (String, String, U256) {
+        let caller = self.sdk.context().contract_caller();
+        
+        if NoteOwner::get(&self.sdk, note_id) != owner || load_note(&self.sdk, &owner, &note_id).is_none() {
+            panic!("Note does not exist");
+        }
+        let note = match load_note_for_reader(&self.sdk, &caller, &note_id) {
+            Some(note) => note,
+            None => panic!("Not authorized to read this note"),
+        };
+        
+        let content = match decrypt_stored_note(&self.sdk, &caller, &note_id) {
+            Ok(content) => content,
+            Err(e) => String::from(e.message()),
+        };
+        (note.title, content, note.updated_at)
+    }
+    
+    #[function_id("getNoteCount()")]
+    fn get_note_count(&self) -> U256 {
+        let caller = self.sdk.context().contract_caller();
+        UserNotesCount::get(&self.sdk, caller)
+    }
+    
+    #[function_id("getLifetimeNoteCount()")]
+    fn get_lifetime_note_count(&self) -> U256 {
+        let caller = self.sdk.context().contract_caller();
+        UserLifetimeNotesCreated::get(&self.sdk, caller)
+    }
+    
+    #[function_id("getNotesList()")]
+    fn get_notes_list(&self) -> (Vec<U256>, Vec<String>, Vec<U256>, Vec<U256>) {
+        // Kept for compatibility: returns one page only, use getNotesPage for the rest
+        let caller = self.sdk.context().contract_caller();
+        let notes = get_notes_pinned_first(&self.sdk, &caller, false);
+        
+        notes_to_columns(&notes)
+    }
+    
+    #[function_id("getNotesListFull()")]
+    fn get_notes_list_full(&self) -> (Vec<U256>, Vec<String>, Vec<U256>, Vec<bool>, Vec<bool>, Vec<U256>) {
+        let caller = self.sdk.context().contract_caller();
+        let notes = get_notes_pinned_first(&self.sdk, &caller, true);
+        
+        let pinned = notes.iter().map(|note| NotePinned::get(&self.sdk, note.id)).collect();
+        let archived = notes.iter().map(|note| NoteArchived::get(&self.sdk, note.id)).collect();
+        let colors = notes.iter().map(|note| NoteColor::get(&self.sdk, note.id)).collect();
+        let (ids, titles, timestamps, _) = notes_to_columns(&notes);
+        (ids, titles, timestamps, pinned, archived, colors)
+    }
+    
+    #[function_id("getNotesPage(uint256,uint256)")]
+    fn get_notes_page(&self, offset: U256, limit: U256) -> (Vec<U256>, Vec<String>, Vec<U256>, Vec<U256>, Vec<bool>) {
+        let caller = self.sdk.context().contract_caller();
+        let notes = get_notes_range(&self.sdk, &caller, offset, limit);
+        
+        let pinned = notes.iter().map(|note| NotePinned::get(&self.sdk, note.id)).collect();
+        let (ids, titles, timestamps, created) = notes_to_columns(&notes);
+        (ids, titles, timestamps, created, pinned)
+    }
+    
+    #[function_id("getNotesSorted(bool)")]
+    fn get_notes_sorted(&self, descending: bool) -> (Vec<U256>, Vec<String>, Vec<U256>) {
+        let caller = self.sdk.context().contract_caller();
+        
+        // Sorting happens in one call, so keep it bounded
+        if UserNotesCount::get(&self.sdk, caller) > U256::from(MAX_NOTES_PER_USER) {
+            panic!("Too many notes to sort, use getNotesPage");
+        }
+        
+        let mut notes = Vec::new();
+        for note_id in load_note_ids(&self.sdk, &caller) {
+            if !NoteArchived::get(&self.sdk, note_id) {
+                if let Some(note) = load_note(&self.sdk, &caller, &note_id) {
+                    notes.push(note);
+                }
+            }
+        }
+        if descending {
+            notes.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        } else {
+            notes.sort_by(|a, b| a.updated_at.cmp(&b.updated_at));
+        }
+        
+        let (ids, titles, timestamps, _) = notes_to_columns(&notes);
+        (ids, titles, timestamps)
+    }
+    
+    #[function_id("getNotesListByRecent()")]
+    fn get_notes_list_by_recent(&self) -> (Vec<U256>, Vec<String>, Vec<U256>) {
+        self.get_notes_sorted(true)
+    }
+    
+    #[function_id("searchNotes(string,uint256,uint256)")]
+    fn search_notes(&self, query: String, offset: U256, limit: U256) -> Vec<U256> {
+        let caller = self.sdk.context().contract_caller();
+        let notes = get_notes_range(&self.sdk, &caller, offset, limit);
+        
+        notes
+            .iter()
+            .filter(|note| match decrypt_stored_note(&self.sdk, &caller, &note.id) {
+                Ok(content) => content.contains(query.as_str()),
+                Err(_) => false,
+            })
+            .map(|note| note.id)
+            .collect()
+    }
+    
+    #[function_id("getLimits()")]
+    fn get_limits(&self) -> (U256, U256) {
+        (U256::from(MAX_TITLE_LEN), U256::from(MAX_CONTENT_LEN))
+    }
+    
+    #[function_id("totalNotes()")]
+    fn total_notes(&self) -> U256 {
+        TotalNotes::get(&self.sdk)
+    }
+    
+    #[function_id("totalUsers()")]
+    fn total_users(&self) -> U256 {
+        TotalUsers::get(&self.sdk)
+    }
+    
+    #[function_id("getStorageUsed(address)")]
+    fn get_storage_used(&self, user: Address) -> U256 {
+        UserStorageBytes::get(&self.sdk, user)
+    }
+    
+    #[function_id("getRemainingQuota()")]
+    fn get_remaining_quota(&self) -> U256 {
+        let caller = self.sdk.context().contract_caller();
+        let limit = note_limit(&self.sdk, &caller);
+        let count = UserNotesCount::get(&self.sdk, caller);
+        
+        // The limit can be lowered below what a user already stores
+        if count >= limit { U256::from(0) } else { limit - count }
+    }
+    
+    #[function_id("owner()")]
+    fn owner(&self) -> Address {
+        ContractOwner::get(&self.sdk)
+    }
+    
+    #[function_id("transferOwnership(address)")]
+    fn transfer_ownership(&mut self, new_owner: Address) {
+        self.only_owner();
+        
+        if new_owner == Address::default() {
+            panic!("Invalid new owner");
+        }
+        ContractOwner::set(&mut self.sdk, new_owner);
+    }
+    
+    #[function_id("pause()")]
+    fn pause(&mut self) {
+        self.only_owner();
+        Paused::set(&mut self.sdk, true);
+    }
+    
+    #[function_id("unpause()")]
+    fn unpause(&mut self) {
+        self.only_owner();
+        Paused::set(&mut self.sdk, false);
+    }
+    
+    #[function_id("paused()")]
+    fn paused(&self) -> bool {
+        Paused::get(&self.sdk)
+    }
+    
+    #[function_id("setUserNoteLimit(address,uint256)")]
+    fn set_user_note_limit(&mut self, user: Address, limit: U256) {
+        self.only_owner();
+        
+        // Lowering below the current count only blocks new notes, nothing is removed
+        UserNoteLimit::set(&mut self.sdk, user, limit);
+    }
+    
+    #[function_id("setRequireEncryptionKey(bool)")]
+    fn set_require_encryption_key(&mut self, required: bool) {
+        self.only_owner();
+        RequireEncryptionKey::set(&mut self.sdk, required);
+    }
+    
+    #[function_id("requireEncryptionKey()")]
+    fn require_encryption_key(&self) -> bool {
+        RequireEncryptionKey::get(&self.sdk)
+    }
+    
+    #[function_id("updateEncryptionKey(bytes)")]
+    fn update_encryption_key(&mut self, new_key: Bytes) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        // Existing notes keep decrypting under the key version they were written with
+        if !PendingEncryptionKeys::get(&self.sdk, caller).is_empty() {
+            panic!("Key rotation in progress");
+        }
+        if new_key.is_empty() {
+            panic!("Encryption key cannot be empty");
+        }
+        set_encryption_key(&mut self.sdk, &caller, new_key);
+    }
+    
+    #[function_id("rotateEncryptionKey(bytes)")]
+    fn rotate_encryption_key(&mut self, new_key: Bytes) {
+        // Migrate every note in one call
+        self.rotate_encryption_key_range(new_key, U256::MAX);
+    }
+    
+    #[function_id("rotateEncryptionKeyRange(bytes,uint256)")]
+    fn rotate_encryption_key_range(&mut self, new_key: Bytes, limit: U256) -> U256 {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        
+        if new_key.is_empty() {
+            panic!("Encryption key cannot be empty");
+        }
+        
+        // Start a rotation, or continue the one already pending for the same key
+        let pending_key = PendingEncryptionKeys::get(&self.sdk, caller);
+        if pending_key.is_empty() {
+            PendingEncryptionKeys::set(&mut self.sdk, caller, new_key.clone());
+            KeyRotationCursor::set(&mut self.sdk, caller, U256::from(0));
+        } else if pending_key != new_key {
+            panic!("Another key rotation is in progress");
+        }
+        
+        // Re-encrypt the next `limit` notes from the cursor under the pending key
+        let count = UserNotesCount::get(&self.sdk, caller);
+        let cursor = KeyRotationCursor::get(&self.sdk, caller);
+        let remaining = if count > cursor { count - cursor } else { U256::from(0) };
+        let end = if remaining < limit { count } else { cursor + limit };
+        
+        let mut index = cursor;
+        while index < end {
+            let note_id = UserNoteIds::get(&self.sdk, caller, index);
+            
+            // Soft-deleted notes are migrated too so they still decrypt after a restore
+            if let Some(note) = load_stored_note(&self.sdk, &caller, &note_id) {
+                if rekey_note(&mut self.sdk, &note).is_err() {
+                    panic!("Failed to decrypt note during key rotation");
+                }
+                
+                if rekey_note_versions(&mut self.sdk, &note).is_err() {
+                    panic!("Failed to decrypt note during key rotation");
+                }
+                
+                let attachment = match load_attachment(&self.sdk, &caller, &note_id) {
+                    Ok(attachment) => attachment,
+                    Err(_) => panic!("Failed to decrypt note during key rotation"),
+                };
+                store_attachment(&mut self.sdk, &caller, &note_id, &attachment);
+            }
+            index += U256::from(1);
+        }
+        
+        // Only commit the new key once every note has been migrated
+        if end >= count {
+            set_encryption_key(&mut self.sdk, &caller, new_key);
+            PendingEncryptionKeys::set(&mut self.sdk, caller, Bytes::new());
+            KeyRotationCursor::set(&mut self.sdk, caller, U256::from(0));
+            return U256::from(0);
+        }
+        
+        KeyRotationCursor::set(&mut self.sdk, caller, end);
+        count - end
+    }
+    
+    #[function_id("hasEncryptionKey(address)")]
+    fn has_encryption_key(&self, user: Address) -> bool {
+        !UserEncryptionKeys::get(&self.sdk, user).is_empty()
+    }
+    
+    #[function_id("exportEncryptionKey()")]
+    fn export_encryption_key(&self) -> Bytes {
+        let caller = self.sdk.context().contract_caller();
+        
+        let key = UserEncryptionKeys::get(&self.sdk, caller);
+        if key.is_empty() {
+            panic!("Encryption key not set");
+        }
+        key
+    }
+    
+    #[function_id("getUserInfo(address)")]
+    fn get_user_info(&self, user: Address) -> (bool, U256, B256) {
+        let key = UserEncryptionKeys::get(&self.sdk, user);
+        let fingerprint = if key.is_empty() { B256::ZERO } else { keccak256(&key) };
+        (RegisteredUsers::get(&self.sdk, user), UserNotesCount::get(&self.sdk, user), fingerprint)
+    }
+    
+    #[function_id("getUserProfile()")]
+    fn get_user_profile(&self) -> (U256, U256, bool) {
+        let caller = self.sdk.context().contract_caller();
+        (self.get_note_count(), self.get_lifetime_note_count(), self.has_encryption_key(caller))
+    }
+    
+    #[function_id("setAllowControlChars(bool)")]
+    fn set_allow_control_chars(&mut self, allowed: bool) {
+        self.when_not_paused();
+        
+        let caller = self.sdk.context().contract_caller();
+        UserAllowsControlChars::set(&mut self.sdk, caller, allowed);
+    }
+    
+    #[function_id("allowsControlChars(address)")]
+    fn allows_control_chars(&self, user: Address) -> bool {
+        UserAllowsControlChars::get(&self.sdk, user)
+    }
+    
+    #[function_id("setCipherMode(uint8)")]
+    fn set_cipher_mode(&mut self, mode: u8) {
+        self.when_not_paused();
+        
+        if mode > CIPHER_CHACHA20_POLY1305 {
+            panic!("Unknown cipher mode");
+        }
+        
+        let caller = self.sdk.context().contract_caller();
+        UserCipherMode::set(&mut self.sdk, caller, U256::from(mode));
+    }
+    
+    #[function_id("getCipherMode(address)")]
+    fn get_cipher_mode(&self, user: Address) -> u8 {
+        cipher_mode_of(&self.sdk, &user)
+    }
+    
+    #[function_id("encryptNote(string)")]
+    fn encrypt_note(&mut self, content: String) -> Bytes {
+        // Get caller address
+        let caller = self.sdk.context().contract_caller();
+        
+        encrypt_as_owner(&mut self.sdk, &caller, content.as_bytes())
+    }
+
+    #[function_id("decryptNote(bytes)")]
+    fn decrypt_note(&self, encrypted_content: Bytes) -> String {
+        // Only ever decrypts with the caller's own key, for ciphertext the caller supplies. The
+        // embedded owner prefix is an integrity check, not authorization, and a successful
+        // decrypt proves nothing about what is in storage; stored notes are read by id via getNote,
+        // which is also where expiry applies
+        let caller = self.sdk.context().contract_caller();
+        let key_version = UserKeyVersion::get(&self.sdk, caller);
+        decrypt_as_owner(&self.sdk, &caller, key_version, cipher_mode_of(&self.sdk, &caller), &encrypted_content)
+    }
+    
+    #[function_id("tryDecryptNote(bytes)")]
+    fn try_decrypt_note(&self, encrypted_content: Bytes) -> (u8, String) {
+        let caller = self.sdk.context().contract_caller();
+        let key_version = UserKeyVersion::get(&self.sdk, caller);
+        match open_ciphertext(&self.sdk, &caller, key_version, cipher_mode_of(&self.sdk, &caller), &encrypted_content) {
+            Ok(plaintext) => (0, plaintext),
+            Err(e) => (e.code(), String::new()),
+        }
+    }
+    
+    // For compatibility with previous architecture where there were two contracts
+    #[function_id("getEncryptionContractAddress()")]
+    fn get_encryption_contract_address(&self) -> Address {
+        // Return this contract's address since all functionality is now here
+        self.sdk.context().contract_address()
+    }
+}
+
+impl<SDK: SharedAPI> SecureNotes<SDK> {
+    // Revert unless the caller is the contract owner; guards every admin method
+    fn only_owner(&self) {
+        let caller = self.sdk.context().contract_caller();
+        if caller != ContractOwner::get(&self.sdk) {
+            panic!("Caller is not the contract owner");
+        }
+    }
+    
+    // Flip the archived flag and emit NoteArchived; callers check ownership and that it changes
+    fn set_archived(&mut self, owner: Address, note_id: U256, archived: bool) {
+        NoteArchived::set(&mut self.sdk, note_id, archived);
+        
+        let note_id_topic = fluentbase_sdk::B256::from(note_id.to_be_bytes::<32>());
+        let archived_data = Bytes::from(U256::from(archived as u8).to_be_bytes::<32>().to_vec());
+        emit_event(&mut self.sdk, EVENT_NOTE_ARCHIVED, archived_data, &[address_topic(&owner), note_id_topic]);
+    }
+    
+    // Revert while the contract is paused; reads stay available
+    fn when_not_paused(&self) {
+        if Paused::get(&self.sdk) {
+            panic!("Contract is paused");
+        }
+    }
+    
+    // The note `caller` may edit (as owner or editor); reverts like getNote when there is none
+    fn load_editable_note(&self, caller: Address, note_id: U256) -> Note {
+        let owner = NoteOwner::get(&self.sdk, note_id);
+        if owner != caller && !NoteEditors::get(&self.sdk, note_id, caller) {
+            if owner == Address::default() {
+                panic!("Note does not exist");
+            }
+            panic!("Not authorized to edit this note");
+        }
+        match load_note(&self.sdk, &owner, &note_id) {
+            Some(note) => note,
+            None => panic!("Note does not exist"),
+        }
+    }
+    
+    // Move a note to the recycle bin and emit NoteDeleted; callers check ownership
+    fn bin_note(&mut self, owner: Address, note_id: U256) {
+        NoteDeleted::set(&mut self.sdk, note_id, true);
+        
+        let total = TotalNotes::get(&self.sdk);
+        TotalNotes::set(&mut self.sdk, total - U256::from(1));
+        
+        self.emit_note_deleted(owner, note_id);
+    }
+    
+    // NoteDeleted carries the owner's note count after the removal (getNoteCount, which still
+    // includes binned notes) so event-sourced mirrors can reconcile without reading storage
+    fn emit_note_deleted(&mut self, owner: Address, note_id: U256) {
+        let note_id_topic = fluentbase_sdk::B256::from(note_id.to_be_bytes::<32>());
+        let count_data = Bytes::from(UserNotesCount::get(&self.sdk, owner).to_be_bytes::<32>().to_vec());
+        emit_event(&mut self.sdk, EVENT_NOTE_DELETED, count_data, &[address_topic(&owner), note_id_topic]);
+    }
+    
+    // Mark an address registered on first use, counting it and emitting UserRegistered
+    // exactly once; never touches its key
+    fn ensure_registered(&mut self, caller: Address) {
+        if !RegisteredUsers::get(&self.sdk, caller) {
+            RegisteredUsers::set(&mut self.sdk, caller, true);
+            
+            let users = TotalUsers::get(&self.sdk);
+            TotalUsers::set(&mut self.sdk, users + U256::from(1));
+            
+            emit_event(&mut self.sdk, EVENT_USER_REGISTERED, Bytes::new(), &[address_topic(&caller)]);
+        }
+    }
+    
+    // Encrypt and store a new note at `note_id` and emit NoteCreated; callers allocate the id
+    fn write_new_note(&mut self, owner: Address, note_id: U256, title: String, content: String) {
+        // Encrypt the content
+        let encrypted_content = seal_note_content(&mut self.sdk, &owner, &note_id, content.as_bytes());
+        self.store_new_note(owner, note_id, title, &content, encrypted_content);
+    }
+    
+    // Store a new note from ciphertext made by seal_note_content and emit NoteCreated;
+    // `content` is its plaintext, used for the content hash and event
+    fn store_new_note(&mut self, owner: Address, note_id: U256, title: String, content: &str, encrypted_content: Bytes) {
+        let created = UserLifetimeNotesCreated::get(&self.sdk, owner);
+        UserLifetimeNotesCreated::set(&mut self.sdk, owner, created + U256::from(1));
+        
+        let key_version = active_key_version(&self.sdk, &owner);
+        
+        // Create new note
+        let timestamp = U256::from(self.sdk.context().block_timestamp());
+        
+        let new_note = Note {
+            id: note_id,
+            owner,
+            encrypted_content,
+            created_at: timestamp,
+            updated_at: timestamp,
+            title: title.clone(),
+            key_version,
+            cipher_mode: cipher_mode_of(&self.sdk, &owner),
+        };
+        
+        // Store the note
+        store_note(&mut self.sdk, &owner, &note_id, &new_note);
+        NoteContentHash::set(&mut self.sdk, note_id, keccak256(content.as_bytes()));
+        NoteLastEditor::set(&mut self.sdk, note_id, owner);
+        NoteCreatedBlock::set(&mut self.sdk, note_id, U256::from(self.sdk.context().block_number()));
+        index_title(&mut self.sdk, &owner, &title, note_id);
+        
+        let note_id_bytes = note_id.to_be_bytes::<32>();
+        let note_id_topic = fluentbase_sdk::B256::from(note_id_bytes);
+        
+        // Ciphertext hash lets indexers verify stored content without reading storage
+        let content_hash_topic = keccak256(&new_note.encrypted_content);
+        
+        // Title, plaintext length and creation time go in the data so indexers can list from logs alone
+        let event_data = encode_note_event_data(&title, content.len(), timestamp);
+        
+        // Emit event with indexed parameters and data
+        emit_event(
+            &mut self.sdk,
+            EVENT_NOTE_CREATED,
+            event_data,
+            &[address_topic(&owner), note_id_topic, content_hash_topic],
+        );
+    }
+    
+    // Deployment logic
+    fn deploy(&mut self) {
+        // The deployer becomes the contract owner
+        let deployer = self.sdk.context().contract_caller();
+        ContractOwner::set(&mut self.sdk, deployer);
+    }
+}
+
+// Define entry point for the contract
+basic_entrypoint!(SecureNotes);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluentbase_sdk::{address, testing::TestingContext, BlockContextV1, ContractContextV1};
+    
+    // The contract as one test caller sees it. Clones of a TestingContext share a single state,
+    // caller included, so every call through an Actor points that state back at its own caller
+    struct Actor {
+        caller: Address,
+        notes: SecureNotes<TestingContext>,
+    }
+    
+    impl Actor {
+        fn select(&self) {
+            let _ = self.notes.sdk.clone().with_contract_context(ContractContextV1 {
+                caller: self.caller,
+                ..Default::default()
+            });
+        }
+    }
+    
+    impl core::ops::Deref for Actor {
+        type Target = SecureNotes<TestingContext>;
+        
+        fn deref(&self) -> &Self::Target {
+            self.select();
+            &self.notes
+        }
+    }
+    
+    impl core::ops::DerefMut for Actor {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            self.select();
+            &mut self.notes
+        }
+    }
+    
+    // The contract on the shared test context, called by `caller`
+    fn as_caller(sdk: &TestingContext, caller: Address) -> Actor {
+        Actor {
+            caller,
+            notes: SecureNotes { sdk: sdk.clone() },
+        }
+    }
+    
+    // Move the shared test context to a new block time; the clone shares its state, so every
+    // contract built on `sdk` sees the new time
+    fn set_block_timestamp(sdk: &TestingContext, timestamp: u64) {
+        let _ = sdk.clone().with_block_context(BlockContextV1 {
+            timestamp,
+            ..Default::default()
+        });
+    }
+
+    // Drain the logs emitted so far as (topics, data) pairs
+    fn take_event_logs(sdk: &TestingContext) -> Vec<(Vec<fluentbase_sdk::B256>, Bytes)> {
+        sdk.take_logs()
+    }
+    
+    // The well-known development key for 0xf39F...2266
+    fn test_signing_key() -> k256::ecdsa::SigningKey {
+        k256::ecdsa::SigningKey::from_slice(&[
+            0xac, 0x09, 0x74, 0xbe, 0xc3, 0x9a, 0x17, 0xe3, 0x6b, 0xa4, 0xa6, 0xb4, 0xd2, 0x38, 0xff, 0x94,
+            0x4b, 0xac, 0xb4, 0x78, 0xcb, 0xed, 0x5e, 0xfc, 0xae, 0x78, 0x4d, 0x7b, 0xf4, 0xf2, 0xff, 0x80,
+        ]).unwrap()
+    }
+    
+    // Sign a createNoteSigned request as 65-byte r || s || v, for the default test chain and contract
+    fn sign_note(key: &k256::ecdsa::SigningKey, author: Address, title: &str, content: &str, nonce: U256) -> Bytes {
+        let digest = signed_note_digest(0, &Address::default(), &author, title, content, nonce);
+        let (signature, recovery_id) = key.sign_prehash_recoverable(digest.as_slice()).unwrap();
+        let mut bytes = signature.to_bytes().to_vec();
+        bytes.push(27 + recovery_id.to_byte());
+        Bytes::from(bytes)
+    }
+    
+    // Decode the (title, contentLength, timestamp) data of NoteCreated/NoteUpdated
+    fn decode_note_event_data(data: &[u8]) -> (String, U256, U256) {
+        let word = |i: usize| U256::from_be_slice(&data[i * 32..(i + 1) * 32]);
+        let offset: usize = word(0).to();
+        let title_len: usize = U256::from_be_slice(&data[offset..offset + 32]).to();
+        let title = String::from_utf8(data[offset + 32..offset + 32 + title_len].to_vec()).unwrap();
+        (title, word(1), word(2))
+    }
+    
+    // Decode the previousTitle that follows the NoteCreated-style fields in NoteUpdated data
+    fn decode_previous_title(data: &[u8]) -> String {
+        let offset: usize = U256::from_be_slice(&data[96..128]).to();
+        let title_len: usize = U256::from_be_slice(&data[offset..offset + 32]).to();
+        String::from_utf8(data[offset + 32..offset + 32 + title_len].to_vec()).unwrap()
+    }
+    
+    #[test]
+    fn test_event_signatures_match_constants() {
+        // Canonical signatures are the source of truth; a drifted constant breaks indexers silently
+        let events: &[(&str, [u8; 32])] = &[
+            ("NoteCreated(address,uint256,bytes32,string,uint256,uint256)", EVENT_NOTE_CREATED),
+            ("NoteUpdated(address,uint256,bytes32,string,uint256,uint256,string)", EVENT_NOTE_UPDATED),
+            ("NoteDeleted(address,uint256,uint256)", EVENT_NOTE_DELETED),
+            ("UserRegistered(address)", EVENT_USER_REGISTERED),
+            ("NoteShared(address,address,uint256)", EVENT_NOTE_SHARED),
+            ("NoteTransferred(address,address,uint256,uint256)", EVENT_NOTE_TRANSFERRED),
+            ("NoteVisibilityChanged(address,uint256,bool)", EVENT_NOTE_VISIBILITY_CHANGED),
+            ("NoteArchived(address,uint256,bool)", EVENT_NOTE_ARCHIVED),
+            ("KeyUpdated(address,bytes32)", EVENT_KEY_UPDATED),
+            ("NoteCategorized(address,uint256,uint256)", EVENT_NOTE_CATEGORIZED),
+            ("NoteAccessed(address,uint256)", EVENT_NOTE_ACCESSED),
+            ("AllNotesDeleted(address,uint256)", EVENT_ALL_NOTES_DELETED),
+        ];
+        for (signature, constant) in events.iter() {
+            assert_eq!(
+                keccak256(signature.as_bytes()).as_slice(),
+                constant.as_slice(),
+                "topic0 constant does not match keccak256(\"{}\")",
+                signature
+            );
+        }
+    }
+    
+    #[test]
+    fn test_note_operations() {
+        // Set up test context
+        let test_address = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let test_title = "Test Note";
+        let test_content = "This is a test note content";
+        
+        let sdk = TestingContext::default().with_contract_context(ContractContextV1 {
+            caller: test_address,
+            ..Default::default()
+        });
+        
+        let mut notes = SecureNotes { sdk: sdk.clone() };
+        
+        // Test creating a note
+        let note_id = notes.create_note(test_title.to_string(), test_content.to_string());
+        
+        // Test getting note count
+        let count = notes.get_note_count();
+        assert_eq!(count, U256::from(1));
+        
+        // Test getting note
+        let (title, content, _, _) = notes.get_note(note_id);
+        assert_eq!(title, test_title);
+        assert_eq!(content, test_content);
+        
+        // Test updating note
+        let updated_title = "Updated Test Note";
+        let updated_content = "This is the updated content";
+        notes.update_note(note_id, updated_title.to_string(), updated_content.to_string());
+        
+        // Verify update
+        let (title, content, _, _) = notes.get_note(note_id);
+        assert_eq!(title, updated_title);
+        assert_eq!(content, updated_content);
+        
+        // Creation time survives the update
+        let (created_at, updated_at) = notes.get_note_timestamps(note_id);
+        assert!(created_at <= updated_at);
+        
+        // Test getting notes list
+        let (ids, titles, _, _) = notes.get_notes_list();
+        assert_eq!(ids.len(), 1);
+        assert_eq!(titles.len(), 1);
+        assert_eq!(titles[0], updated_title);
+        
+        // Test deleting note
+        notes.delete_note(note_id);
+        
+        // Verify deletion
+        let (ids, _, _, _) = notes.get_notes_list();
+        assert!(ids.is_empty());
+        
+        // Purging removes it for good
+        notes.purge_note(note_id);
+        let count = notes.get_note_count();
+        assert_eq!(count, U256::from(0));
+    }
+    
+    #[test]
+    fn test_created_at_survives_update_and_delete() {
+        let test_address = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default().with_contract_context(ContractContextV1 {
+            caller: test_address,
+            ..Default::default()
+        });
+        
+        let mut notes = SecureNotes { sdk: sdk.clone() };
+        
+        set_block_timestamp(&sdk, 100);
+        let first_id = notes.create_note("First".to_string(), "first".to_string());
+        
+        set_block_timestamp(&sdk, 200);
+        let second_id = notes.create_note("Second".to_string(), "second".to_string());
+        
+        // Updating moves the modified time but keeps the creation time
+        set_block_timestamp(&sdk, 300);
+        notes.update_note(second_id, "Second".to_string(), "edited".to_string());
+        assert_eq!(notes.get_note_timestamps(second_id), (U256::from(200), U256::from(300)));
+        
+        // get_note reports both times
+        let (_, _, created_at, updated_at) = notes.get_note(second_id);
+        assert_eq!(created_at, U256::from(200));
+        assert_eq!(updated_at, U256::from(300));
+        
+        // And so does the listing
+        let (_, _, updated, created) = notes.get_notes_list();
+        assert_eq!(updated, vec![U256::from(100), U256::from(300)]);
+        assert_eq!(created, vec![U256::from(100), U256::from(200)]);
+        
+        // Purging the first note leaves the second, and its times, under its own id
+        notes.purge_note(first_id);
+        assert_eq!(notes.get_note_timestamps(first_id), (U256::from(0), U256::from(0)));
+        assert_eq!(notes.get_note_timestamps(second_id), (U256::from(200), U256::from(300)));
+    }
+    
+    #[test]
+    fn test_note_created_block() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let _ = sdk.clone().with_block_context(BlockContextV1 {
+            timestamp: 100,
+            number: 42,
+            ..Default::default()
+        });
+        let note_id = notes.create_note("Audit".to_string(), "entry".to_string());
+        
+        // Later blocks don't move the creation block, updates included
+        let _ = sdk.clone().with_block_context(BlockContextV1 {
+            timestamp: 200,
+            number: 43,
+            ..Default::default()
+        });
+        notes.update_note(note_id, "Audit".to_string(), "edited".to_string());
+        assert_eq!(notes.get_note_block(note_id), U256::from(42));
+        
+        let stranger_notes = as_caller(&sdk, stranger);
+        assert_eq!(stranger_notes.get_note_block(note_id), U256::from(0));
+        
+        notes.purge_note(note_id);
+        assert_eq!(NoteCreatedBlock::get(&sdk, note_id), U256::from(0));
+    }
+    
+    #[test]
+    fn test_try_decrypt_note_status_codes() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let encrypted = notes.encrypt_note("secret".to_string());
+        assert_eq!(notes.try_decrypt_note(encrypted.clone()), (0, "secret".to_string()));
+        
+        // Too short to hold prefix, nonce and tag
+        assert_eq!(notes.try_decrypt_note(Bytes::from(encrypted[..30].to_vec())), (1, String::new()));
+        
+        // Someone else's ciphertext
+        let stranger_notes = as_caller(&sdk, stranger);
+        assert_eq!(stranger_notes.try_decrypt_note(encrypted.clone()), (2, String::new()));
+        
+        // Authentic ciphertext of bytes that aren't UTF-8
+        let mut raw_sdk = sdk.clone();
+        let invalid_utf8 = encrypt_as_owner(&mut raw_sdk, &owner, &[0xff, 0xfe, 0xfd]);
+        assert_eq!(notes.try_decrypt_note(invalid_utf8.clone()), (3, String::new()));
+        assert_eq!(notes.decrypt_note(invalid_utf8), "Error: Decryption failed");
+        
+        // A flipped tag bit
+        let mut tampered = encrypted.to_vec();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 0x01;
+        assert_eq!(notes.try_decrypt_note(Bytes::from(tampered)), (4, String::new()));
+    }
+    
+    #[test]
+    fn test_decrypt_note_ignores_spoofed_prefix() {
+        let victim = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let attacker = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, victim);
+        notes.register_user(Bytes::from("victim key".as_bytes().to_vec()));
+        let note_id = notes.create_note("Secret".to_string(), "victim content".to_string());
+        let stored = load_note_content(&sdk, &note_id);
+        
+        // Replaying the victim's stored ciphertext doesn't make the attacker its owner
+        let attacker_notes = as_caller(&sdk, attacker);
+        assert_eq!(attacker_notes.try_decrypt_note(stored.clone()), (2, String::new()));
+        
+        // Re-prefixing it with the attacker's address only gets it tried under the attacker's key
+        let mut spoofed = attacker.to_vec();
+        spoofed.extend_from_slice(&stored[OWNER_PREFIX_LEN..]);
+        assert_eq!(attacker_notes.try_decrypt_note(Bytes::from(spoofed)), (4, String::new()));
+        
+        // Reading by id checks ownership in storage
+        assert!(!attacker_notes.try_get_note(note_id).0);
+        assert_eq!(notes.get_note(note_id).1, "victim content");
+    }
+    
+    #[test]
+    fn test_encryption_round_trip_and_tamper_detection() {
+        let test_address = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default().with_contract_context(ContractContextV1 {
+            caller: test_address,
+            ..Default::default()
+        });
+        
+        let mut notes = SecureNotes { sdk: sdk.clone() };
+        notes.register_user(Bytes::from("a short user secret".as_bytes().to_vec()));
+        
+        // Same plaintext twice yields different ciphertext thanks to the per-user nonce counter
+        let first = notes.encrypt_note("secret content".to_string());
+        let second = notes.encrypt_note("secret content".to_string());
+        assert_ne!(first, second);
+        assert_eq!(first.len(), OWNER_PREFIX_LEN + NONCE_LEN + "secret content".len() + TAG_LEN);
+        
+        // Round trip
+        assert_eq!(notes.decrypt_note(first.clone()), "secret content");
+        
+        // Flipping a ciphertext bit fails authentication
+        let mut tampered = first.to_vec();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 0x01;
+        assert_eq!(notes.decrypt_note(Bytes::from(tampered)), "Error: authentication failed");
+        
+        // Truncated data is rejected before decrypting
+        assert_eq!(notes.decrypt_note(Bytes::from(first[..30].to_vec())), "Error: Invalid data format");
+    }
+    
+    #[test]
+    fn test_notes_pagination() {
+        let test_address = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default().with_contract_context(ContractContextV1 {
+            caller: test_address,
+            ..Default::default()
+        });
+        
+        let mut notes = SecureNotes { sdk: sdk.clone() };
+        for i in 0..5 {
+            notes.create_note(format!("Note {}", i), "content".to_string());
+        }
+        
+        // Full first page
+        let (ids, titles, _, _, _) = notes.get_notes_page(U256::from(0), U256::from(3));
+        assert_eq!(ids, vec![U256::from(0), U256::from(1), U256::from(2)]);
+        assert_eq!(titles[2], "Note 2");
+        
+        // Partial final page
+        let (ids, titles, updated, created, _) = notes.get_notes_page(U256::from(3), U256::from(3));
+        assert_eq!(ids, vec![U256::from(3), U256::from(4)]);
+        assert_eq!(titles.len(), 2);
+        assert_eq!(updated.len(), 2);
+        assert_eq!(created.len(), 2);
+        
+        // Offset at or past the end yields nothing
+        let (ids, _, _, _, _) = notes.get_notes_page(U256::from(5), U256::from(3));
+        assert!(ids.is_empty());
+        let (ids, _, _, _, _) = notes.get_notes_page(U256::from(50), U256::from(3));
+        assert!(ids.is_empty());
+        
+        // A huge limit is clamped rather than overflowing
+        let (ids, _, _, _, _) = notes.get_notes_page(U256::from(1), U256::MAX);
+        assert_eq!(ids.len(), 4);
+    }
+    
+    #[test]
+    fn test_get_note_owner() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Mine".to_string(), "content".to_string());
+        
+        // Anyone can look up the owner without being able to read the note
+        let notes = as_caller(&sdk, stranger);
+        assert_eq!(notes.get_note_owner(note_id), owner);
+        assert_eq!(notes.get_note_owner(U256::from(42)), Address::default());
+    }
+    
+    #[test]
+    fn test_note_versions() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Draft".to_string(), "one".to_string());
+        assert_eq!(notes.get_note_version_count(note_id), U256::from(0));
+        
+        notes.update_note(note_id, "Draft".to_string(), "two".to_string());
+        notes.update_note(note_id, "Draft".to_string(), "three".to_string());
+        assert_eq!(notes.get_note_version_count(note_id), U256::from(2));
+        assert_eq!(notes.get_note_version(note_id, U256::from(0)), "one");
+        assert_eq!(notes.get_note_version(note_id, U256::from(1)), "two");
+        
+        // Reverting restores the old content and keeps the replaced one as a version
+        notes.revert_note_to_version(note_id, U256::from(0));
+        let (title, content, _, _) = notes.get_note(note_id);
+        assert_eq!(title, "Draft");
+        assert_eq!(content, "one");
+        assert_eq!(notes.get_note_version_count(note_id), U256::from(3));
+        assert_eq!(notes.get_note_version(note_id, U256::from(2)), "three");
+    }
+    
+    #[test]
+    fn test_note_versions_capped() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Draft".to_string(), "v0".to_string());
+        for i in 1..=MAX_NOTE_VERSIONS + 2 {
+            notes.update_note(note_id, "Draft".to_string(), format!("v{}", i));
+        }
+        
+        // The two oldest versions were dropped
+        assert_eq!(notes.get_note_version_count(note_id), U256::from(MAX_NOTE_VERSIONS));
+        assert_eq!(notes.get_note_version(note_id, U256::from(0)), "v2");
+        assert_eq!(notes.get_note_version(note_id, U256::from(MAX_NOTE_VERSIONS - 1)), format!("v{}", MAX_NOTE_VERSIONS + 1));
+        
+        // Purging wipes the history
+        notes.purge_note(note_id);
+        assert_eq!(NoteVersionCount::get(&sdk, note_id), U256::from(0));
+        assert!(NoteVersionContent::get(&sdk, note_id, U256::from(0)).is_empty());
+    }
+    
+    #[test]
+    #[should_panic(expected = "Version could not be decrypted")]
+    fn test_revert_to_undecryptable_version() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Draft".to_string(), "one".to_string());
+        notes.update_note(note_id, "Draft".to_string(), "two".to_string());
+        
+        // Corrupt the stored version so it no longer authenticates
+        let mut raw_sdk = sdk.clone();
+        let mut ciphertext = NoteVersionContent::get(&sdk, note_id, U256::from(0)).to_vec();
+        let last = ciphertext.len() - 1;
+        ciphertext[last] ^= 0xff;
+        NoteVersionContent::set(&mut raw_sdk, note_id, U256::from(0), Bytes::from(ciphertext));
+        
+        notes.revert_note_to_version(note_id, U256::from(0));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Version does not exist")]
+    fn test_note_version_out_of_range() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Draft".to_string(), "one".to_string());
+        notes.get_note_version(note_id, U256::from(0));
+    }
+    
+    #[test]
+    fn test_lifetime_note_count() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let first = notes.create_note("First".to_string(), "first".to_string());
+        let second = notes.create_note("Second".to_string(), "second".to_string());
+        notes.create_note("Third".to_string(), "third".to_string());
+        
+        notes.delete_note(first);
+        notes.purge_note(first);
+        notes.purge_note(second);
+        assert_eq!(notes.get_lifetime_note_count(), U256::from(3));
+        assert_eq!(notes.get_note_count(), U256::from(1));
+    }
+    
+    #[test]
+    fn test_update_note_title_only() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        set_block_timestamp(&sdk, 100);
+        let mut notes = as_caller(&sdk, owner);
+        notes.register_user(Bytes::from("owner key".as_bytes().to_vec()));
+        let note_id = notes.create_note("Draft".to_string(), "twelve bytes".to_string());
+        let ciphertext = load_note_content(&sdk, &note_id);
+        take_event_logs(&sdk);
+        
+        set_block_timestamp(&sdk, 200);
+        notes.update_note_title(note_id, "Final".to_string());
+        assert_eq!(
+            notes.get_note(note_id),
+            ("Final".to_string(), "twelve bytes".to_string(), U256::from(100), U256::from(200))
+        );
+        
+        // The ciphertext is untouched and no version is recorded
+        assert_eq!(load_note_content(&sdk, &note_id), ciphertext);
+        assert_eq!(notes.get_note_version_count(note_id), U256::from(0));
+        assert_eq!(notes.find_note_by_title("Final".to_string()), (true, note_id));
+        assert_eq!(notes.find_note_by_title("Draft".to_string()), (false, U256::from(0)));
+        
+        let logs = take_event_logs(&sdk);
+        let (topics, data) = logs.iter().find(|(topics, _)| topics[0].as_slice() == EVENT_NOTE_UPDATED).unwrap();
+        assert_eq!(topics[3], keccak256(&ciphertext));
+        assert_eq!(decode_note_event_data(data), ("Final".to_string(), U256::from(12), U256::from(200)));
+        assert_eq!(decode_previous_title(data), "Draft");
+    }
+    
+    #[test]
+    fn test_update_note_content_only() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        set_block_timestamp(&sdk, 100);
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Draft".to_string(), "first".to_string());
+        take_event_logs(&sdk);
+        
+        set_block_timestamp(&sdk, 200);
+        notes.update_note_content(note_id, "second".to_string());
+        assert_eq!(
+            notes.get_note(note_id),
+            ("Draft".to_string(), "second".to_string(), U256::from(100), U256::from(200))
+        );
+        assert_eq!(notes.get_note_version(note_id, U256::from(0)), "first");
+        
+        let logs = take_event_logs(&sdk);
+        let (_, data) = logs.iter().find(|(topics, _)| topics[0].as_slice() == EVENT_NOTE_UPDATED).unwrap();
+        assert_eq!(decode_note_event_data(data), ("Draft".to_string(), U256::from(6), U256::from(200)));
+    }
+    
+    #[test]
+    fn test_append_to_note() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        set_block_timestamp(&sdk, 100);
+        let mut notes = as_caller(&sdk, owner);
+        notes.register_user(Bytes::from("owner key".as_bytes().to_vec()));
+        let note_id = notes.create_note("Log".to_string(), "boot".to_string());
+        
+        set_block_timestamp(&sdk, 200);
+        notes.append_to_note(note_id, "\nstart".to_string());
+        set_block_timestamp(&sdk, 300);
+        notes.append_to_note(note_id, "\nstop".to_string());
+        
+        assert_eq!(
+            notes.get_note(note_id),
+            ("Log".to_string(), "boot\nstart\nstop".to_string(), U256::from(100), U256::from(300))
+        );
+        
+        let logs = take_event_logs(&sdk);
+        let (_, data) = logs.iter().rev().find(|(topics, _)| topics[0].as_slice() == EVENT_NOTE_UPDATED).unwrap();
+        assert_eq!(decode_note_event_data(data), ("Log".to_string(), U256::from(15), U256::from(300)));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Existing content could not be decrypted")]
+    fn test_append_to_undecryptable_note() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Log".to_string(), "boot".to_string());
+        
+        // Corrupt the stored ciphertext so it no longer authenticates
+        let mut raw_sdk = sdk.clone();
+        let mut ciphertext = load_note_content(&sdk, &note_id).to_vec();
+        let last = ciphertext.len() - 1;
+        ciphertext[last] ^= 0xff;
+        store_note_content(&mut raw_sdk, &note_id, &Bytes::from(ciphertext));
+        
+        notes.append_to_note(note_id, " more".to_string());
+    }
+    
+    #[test]
+    fn test_collaborator_is_last_editor() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let collaborator = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        set_block_timestamp(&sdk, 100);
+        let mut notes = as_caller(&sdk, owner);
+        notes.register_user(Bytes::from("owner key".as_bytes().to_vec()));
+        let note_id = notes.create_note("Plan".to_string(), "draft".to_string());
+        assert_eq!(notes.get_note_meta(note_id), (owner, owner, U256::from(100), CONTENT_TYPE_PLAIN));
+        as_caller(&sdk, collaborator).register_user(Bytes::from("collaborator key".as_bytes().to_vec()));
+        notes.share_note(note_id, collaborator);
+        notes.set_note_editor(note_id, collaborator, true);
+        assert!(notes.is_note_editor(note_id, collaborator));
+        
+        // The collaborator's edit stays encrypted under the owner's key
+        set_block_timestamp(&sdk, 200);
+        let mut collaborator_notes = as_caller(&sdk, collaborator);
+        collaborator_notes.update_note(note_id, "Plan".to_string(), "reviewed".to_string());
+        assert_eq!(collaborator_notes.get_note_meta(note_id), (owner, collaborator, U256::from(200), CONTENT_TYPE_PLAIN));
+        assert_eq!(notes.get_note(note_id).1, "reviewed");
+        
+        notes.update_note(note_id, "Plan".to_string(), "final".to_string());
+        assert_eq!(notes.get_note_meta(note_id), (owner, owner, U256::from(200), CONTENT_TYPE_PLAIN));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Not authorized to edit this note")]
+    fn test_share_alone_does_not_grant_edit() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let reader = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Plan".to_string(), "draft".to_string());
+        let mut reader_notes = as_caller(&sdk, reader);
+        reader_notes.register_user(Bytes::from("reader key".as_bytes().to_vec()));
+        notes.share_note(note_id, reader);
+        assert!(!notes.is_note_editor(note_id, reader));
+        
+        reader_notes.update_note(note_id, "Plan".to_string(), "vandalised".to_string());
+    }
+    
+    #[test]
+    fn test_revoking_share_drops_edit_access() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let reader = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Plan".to_string(), "draft".to_string());
+        as_caller(&sdk, reader).register_user(Bytes::from("reader key".as_bytes().to_vec()));
+        notes.share_note(note_id, reader);
+        notes.set_note_editor(note_id, reader, true);
+        
+        // Sharing again later starts from read-only
+        notes.revoke_share(note_id, reader);
+        assert!(!notes.is_note_editor(note_id, reader));
+        notes.share_note(note_id, reader);
+        assert!(!notes.is_note_editor(note_id, reader));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Editor must be a share recipient")]
+    fn test_editor_must_be_share_recipient() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Plan".to_string(), "draft".to_string());
+        notes.set_note_editor(note_id, stranger, true);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Not authorized to edit this note")]
+    fn test_stranger_cannot_update_note() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Plan".to_string(), "draft".to_string());
+        
+        as_caller(&sdk, stranger).update_note(note_id, "Plan".to_string(), "vandalised".to_string());
+    }
+    
+    #[test]
+    fn test_update_and_delete_return_note_id() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.create_note("First".to_string(), "one".to_string());
+        let note_id = notes.create_note("Second".to_string(), "two".to_string());
+        
+        assert_eq!(notes.update_note(note_id, "Second".to_string(), "edited".to_string()), note_id);
+        assert_eq!(notes.delete_note(note_id), note_id);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note does not exist")]
+    fn test_update_missing_note_reverts() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.update_note(U256::from(7), "Ghost".to_string(), "nothing here".to_string());
+    }
+    
+    #[test]
+    fn test_delete_all_notes() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let other = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut other_notes = as_caller(&sdk, other);
+        let kept = other_notes.create_note("Kept".to_string(), "kept".to_string());
+        
+        let mut notes = as_caller(&sdk, owner);
+        let mut ids = Vec::new();
+        for i in 0..4 {
+            ids.push(notes.create_note(format!("Note {}", i), "content".to_string()));
+        }
+        notes.set_note_tags(ids[0], vec!["tag".to_string()]);
+        notes.delete_note(ids[1]);
+        
+        notes.delete_all_notes();
+        assert_eq!(notes.get_note_count(), U256::from(0));
+        assert_eq!(notes.get_storage_used(owner), U256::from(0));
+        for note_id in &ids {
+            assert_eq!(NoteOwner::get(&sdk, *note_id), Address::default());
+            assert!(load_note_content(&sdk, note_id).is_empty());
+            assert!(NoteTitle::get(&sdk, *note_id).is_empty());
+            assert!(!NoteDeleted::get(&sdk, *note_id));
+        }
+        assert_eq!(NoteTagCount::get(&sdk, ids[0]), U256::from(0));
+        assert_eq!(notes.find_note_by_title("Note 0".to_string()), (false, U256::from(0)));
+        
+        // Other users are untouched
+        assert_eq!(other_notes.get_note(kept).1, "kept");
+    }
+    
+    #[test]
+    fn test_delete_all_notes_summary_event() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let mut ids = Vec::new();
+        for i in 0..3 {
+            ids.push(notes.create_note(format!("Note {}", i), "content".to_string()));
+        }
+        take_event_logs(&sdk);
+        
+        notes.delete_all_notes();
+        assert_eq!(notes.get_note_count(), U256::from(0));
+        for note_id in &ids {
+            assert!(!notes.try_get_note(*note_id).0);
+        }
+        
+        // A single summary event with how many notes were cleared, and no per-note ones
+        let logs = take_event_logs(&sdk);
+        assert!(logs.iter().all(|(topics, _)| topics[0].as_slice() != EVENT_NOTE_DELETED));
+        let summaries: Vec<_> = logs
+            .into_iter()
+            .filter(|(topics, _)| topics[0].as_slice() == EVENT_ALL_NOTES_DELETED)
+            .collect();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].0[1], address_topic(&owner));
+        assert_eq!(U256::from_be_slice(&summaries[0].1), U256::from(3));
+    }
+    
+    #[test]
+    fn test_delete_notes_batch() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut stranger_notes = as_caller(&sdk, stranger);
+        let foreign = stranger_notes.create_note("Theirs".to_string(), "theirs".to_string());
+        
+        let mut notes = as_caller(&sdk, owner);
+        let first = notes.create_note("First".to_string(), "first".to_string());
+        let second = notes.create_note("Second".to_string(), "second".to_string());
+        let third = notes.create_note("Third".to_string(), "third".to_string());
+        take_event_logs(&sdk);
+        
+        // A nonexistent id, someone else's note and a repeat are skipped
+        notes.delete_notes_batch(vec![first, U256::from(99), foreign, third, first]);
+        let (ids, _, _, _) = notes.get_notes_list();
+        assert_eq!(ids, vec![second]);
+        let (binned, _, _, _) = notes.get_deleted_notes();
+        assert_eq!(binned, vec![first, third]);
+        assert!(stranger_notes.try_get_note(foreign).0);
+        
+        // One NoteDeleted per note actually binned
+        let deleted: Vec<_> = take_event_logs(&sdk)
+            .into_iter()
+            .filter(|(topics, _)| topics[0].as_slice() == EVENT_NOTE_DELETED)
+            .map(|(topics, _)| U256::from_be_slice(topics[2].as_slice()))
+            .collect();
+        assert_eq!(deleted, vec![first, third]);
+    }
+    
+    #[test]
+    fn test_note_deleted_event_carries_count() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let first = notes.create_note("First".to_string(), "first".to_string());
+        let second = notes.create_note("Second".to_string(), "second".to_string());
+        take_event_logs(&sdk);
+        
+        // Binning keeps the note in the count, purging drops it
+        notes.delete_note(first);
+        notes.purge_note(second);
+        
+        let deleted: Vec<_> = take_event_logs(&sdk)
+            .into_iter()
+            .filter(|(topics, _)| topics[0].as_slice() == EVENT_NOTE_DELETED)
+            .map(|(topics, data)| {
+                assert_eq!(topics[1], address_topic(&owner));
+                (U256::from_be_slice(topics[2].as_slice()), U256::from_be_slice(&data))
+            })
+            .collect();
+        assert_eq!(deleted, vec![(first, U256::from(2)), (second, U256::from(1))]);
+        assert_eq!(notes.get_note_count(), U256::from(1));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note is locked")]
+    fn test_update_locked_note_reverts() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Important".to_string(), "keep".to_string());
+        notes.lock_note(note_id);
+        notes.update_note(note_id, "Important".to_string(), "overwritten".to_string());
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note is locked")]
+    fn test_delete_locked_note_reverts() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Important".to_string(), "keep".to_string());
+        notes.lock_note(note_id);
+        notes.delete_note(note_id);
+    }
+    
+    #[test]
+    fn test_delete_all_notes_keeps_locked_notes() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let first = notes.create_note("First".to_string(), "first".to_string());
+        let locked = notes.create_note("Locked".to_string(), "keep".to_string());
+        let last = notes.create_note("Last".to_string(), "last".to_string());
+        notes.lock_note(locked);
+        take_event_logs(&sdk);
+        
+        notes.delete_all_notes();
+        assert_eq!(notes.get_note_count(), U256::from(1));
+        assert_eq!(notes.get_note(locked).1, "keep");
+        assert!(!notes.try_get_note(first).0);
+        assert!(!notes.try_get_note(last).0);
+        
+        let summary = take_event_logs(&sdk)
+            .into_iter()
+            .find(|(topics, _)| topics[0].as_slice() == EVENT_ALL_NOTES_DELETED)
+            .unwrap();
+        assert_eq!(U256::from_be_slice(&summary.1), U256::from(2));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note is locked")]
+    fn test_transfer_locked_note_reverts() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let recipient = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        as_caller(&sdk, recipient).register_user(Bytes::from("recipient key".as_bytes().to_vec()));
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Important".to_string(), "keep".to_string());
+        notes.lock_note(note_id);
+        notes.transfer_note(note_id, recipient);
+    }
+    
+    #[test]
+    fn test_note_lock_is_per_note() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let locked = notes.create_note("Locked".to_string(), "keep".to_string());
+        let other = notes.create_note("Other".to_string(), "other".to_string());
+        let last = notes.create_note("Last".to_string(), "last".to_string());
+        notes.lock_note(locked);
+        
+        // Removing other notes doesn't move the lock onto anything else
+        notes.purge_note(other);
+        notes.update_note(last, "Last".to_string(), "edited".to_string());
+        assert_eq!(notes.get_note(last).1, "edited");
+        
+        // Batch deletes skip the locked note
+        notes.delete_notes_batch(vec![locked, last]);
+        assert!(notes.try_get_note(locked).0);
+        assert!(!notes.try_get_note(last).0);
+        
+        // Editable again once unlocked
+        notes.unlock_note(locked);
+        notes.update_note(locked, "Locked".to_string(), "changed".to_string());
+        assert_eq!(notes.get_note(locked).1, "changed");
+    }
+    
+    #[test]
+    fn test_note_exists() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Mine".to_string(), "content".to_string());
+        assert!(notes.note_exists(owner, note_id));
+        assert!(!notes.note_exists(stranger, note_id));
+        assert!(!notes.note_exists(owner, U256::from(42)));
+        
+        // Agrees with tryGetNote through the recycle bin and purge
+        notes.delete_note(note_id);
+        assert!(!notes.note_exists(owner, note_id));
+        assert!(!notes.try_get_note(note_id).0);
+        notes.restore_note(note_id);
+        assert!(notes.note_exists(owner, note_id));
+        assert!(notes.try_get_note(note_id).0);
+        notes.purge_note(note_id);
+        assert!(!notes.note_exists(owner, note_id));
+        assert!(!notes.try_get_note(note_id).0);
+    }
+    
+    #[test]
+    fn test_get_notes_sorted() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        set_block_timestamp(&sdk, 300);
+        let late = notes.create_note("Late".to_string(), "late".to_string());
+        set_block_timestamp(&sdk, 100);
+        let early = notes.create_note("Early".to_string(), "early".to_string());
+        set_block_timestamp(&sdk, 200);
+        let middle = notes.create_note("Middle".to_string(), "middle".to_string());
+        
+        let (ids, titles, timestamps) = notes.get_notes_sorted(false);
+        assert_eq!(ids, vec![early, middle, late]);
+        assert_eq!(titles, vec!["Early".to_string(), "Middle".to_string(), "Late".to_string()]);
+        assert_eq!(timestamps, vec![U256::from(100), U256::from(200), U256::from(300)]);
+        
+        let (ids, _, _) = notes.get_notes_sorted(true);
+        assert_eq!(ids, vec![late, middle, early]);
+        
+        // Sorted by last update, not creation
+        set_block_timestamp(&sdk, 400);
+        notes.update_note(early, "Early".to_string(), "edited".to_string());
+        let (ids, _, _) = notes.get_notes_sorted(true);
+        assert_eq!(ids, vec![early, late, middle]);
+    }
+    
+    #[test]
+    fn test_get_notes_list_by_recent() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        set_block_timestamp(&sdk, 100);
+        let first = notes.create_note("First".to_string(), "one".to_string());
+        set_block_timestamp(&sdk, 200);
+        let second = notes.create_note("Second".to_string(), "two".to_string());
+        set_block_timestamp(&sdk, 300);
+        let third = notes.create_note("Third".to_string(), "three".to_string());
+        
+        let (ids, titles, timestamps) = notes.get_notes_list_by_recent();
+        assert_eq!(ids, vec![third, second, first]);
+        assert_eq!(titles, vec!["Third".to_string(), "Second".to_string(), "First".to_string()]);
+        assert_eq!(timestamps, vec![U256::from(300), U256::from(200), U256::from(100)]);
+    }
+    
+    #[test]
+    fn test_search_notes() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let groceries = notes.create_note("Groceries".to_string(), "buy milk and eggs".to_string());
+        notes.create_note("Todo".to_string(), "write tests".to_string());
+        let recipe = notes.create_note("Recipe".to_string(), "whisk the eggs".to_string());
+        let all = U256::from(MAX_PAGE_SIZE);
+        
+        assert_eq!(notes.search_notes("eggs".to_string(), U256::from(0), all), vec![groceries, recipe]);
+        
+        // Case-sensitive, and titles aren't searched
+        assert!(notes.search_notes("Eggs".to_string(), U256::from(0), all).is_empty());
+        assert!(notes.search_notes("Recipe".to_string(), U256::from(0), all).is_empty());
+        
+        // Only the requested window is scanned
+        assert_eq!(notes.search_notes("eggs".to_string(), U256::from(1), U256::from(1)), Vec::<U256>::new());
+        assert_eq!(notes.search_notes("eggs".to_string(), U256::from(1), U256::from(2)), vec![recipe]);
+        
+        // An empty query matches everything in the window
+        assert_eq!(notes.search_notes(String::new(), U256::from(0), all).len(), 3);
+    }
+    
+    #[test]
+    fn test_note_integrity() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Checked".to_string(), "original".to_string());
+        assert_eq!(notes.get_note_hash(note_id), keccak256("original".as_bytes()));
+        assert!(notes.verify_note_integrity(note_id));
+        
+        notes.update_note(note_id, "Checked".to_string(), "edited".to_string());
+        assert_eq!(notes.get_note_hash(note_id), keccak256("edited".as_bytes()));
+        assert!(notes.verify_note_integrity(note_id));
+        
+        // A valid ciphertext of other content no longer matches the recorded hash
+        let mut raw_sdk = sdk.clone();
+        let other = seal_note_content(&mut raw_sdk, &owner, &note_id, "other".as_bytes());
+        store_note_content(&mut raw_sdk, &note_id, &other);
+        assert!(!notes.verify_note_integrity(note_id));
+        
+        // Nor does ciphertext that fails to open
+        store_note_content(&mut raw_sdk, &note_id, &Bytes::from(vec![0u8; 64]));
+        assert!(!notes.verify_note_integrity(note_id));
+    }
+    
+    #[test]
+    fn test_note_stats() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Stats".to_string(), "héllo wörld".to_string());
+        
+        // Characters, not bytes
+        assert_eq!(notes.get_note_stats(note_id), (U256::from(11), U256::from(2)));
+        
+        // Runs of whitespace, including leading and trailing, don't add words
+        notes.update_note(note_id, "Stats".to_string(), "  one   two\tthree \n".to_string());
+        assert_eq!(notes.get_note_stats(note_id), (U256::from(19), U256::from(3)));
+        
+        // An empty note has no characters and no words
+        let empty = notes.create_note("Empty".to_string(), String::new());
+        assert_eq!(notes.get_note_stats(empty), (U256::from(0), U256::from(0)));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Not authorized to read this note")]
+    fn test_note_stats_requires_read_access() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Private".to_string(), "three words here".to_string());
+        
+        let notes = as_caller(&sdk, stranger);
+        notes.get_note_stats(note_id);
+    }
+    
+    #[test]
+    fn test_note_event_data_matches_stored_note() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        set_block_timestamp(&sdk, 100);
+        let note_id = notes.create_note("Event title".to_string(), "twelve bytes".to_string());
+        
+        // NoteCreated keeps its indexed topics and carries (title, contentLength, createdAt)
+        let logs = take_event_logs(&sdk);
+        let (topics, data) = logs.iter().find(|(topics, _)| topics[0].as_slice() == EVENT_NOTE_CREATED).unwrap();
+        assert_eq!(topics[1], address_topic(&owner));
+        assert_eq!(topics[2], fluentbase_sdk::B256::from(note_id.to_be_bytes::<32>()));
+        assert_eq!(topics[3], keccak256(&load_note_content(&sdk, &note_id)));
+        let (title, content_len, created_at) = decode_note_event_data(data);
+        assert_eq!(title, NoteTitle::get(&sdk, note_id));
+        assert_eq!(content_len, U256::from(12));
+        assert_eq!(created_at, NoteCreatedAt::get(&sdk, note_id));
+        
+        // NoteUpdated carries (title, contentLength, updatedAt, previousTitle)
+        set_block_timestamp(&sdk, 200);
+        notes.update_note(note_id, "Renamed".to_string(), "a much longer body".to_string());
+        let logs = take_event_logs(&sdk);
+        let (_, data) = logs.iter().find(|(topics, _)| topics[0].as_slice() == EVENT_NOTE_UPDATED).unwrap();
+        let (title, content_len, updated_at) = decode_note_event_data(data);
+        assert_eq!(title, "Renamed");
+        assert_eq!(content_len, U256::from(18));
+        assert_eq!(updated_at, NoteUpdatedAt::get(&sdk, note_id));
+        assert_eq!(updated_at, U256::from(200));
+        assert_eq!(decode_previous_title(data), "Event title");
+    }
+    
+    #[test]
+    fn test_purge_clears_note_and_keeps_other_ids() {
+        let test_address = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default().with_contract_context(ContractContextV1 {
+            caller: test_address,
+            ..Default::default()
+        });
+        
+        let mut notes = SecureNotes { sdk: sdk.clone() };
+        let zero = notes.create_note("Zero".to_string(), "zero".to_string());
+        let middle = notes.create_note("One".to_string(), "one".to_string());
+        let last = notes.create_note("Two".to_string(), "two".to_string());
+        
+        // Purge the middle note; the last one keeps its id and takes its place in the list
+        notes.purge_note(middle);
+        assert_eq!(notes.get_note_count(), U256::from(2));
+        let (title, content, _, _) = notes.get_note(last);
+        assert_eq!(title, "Two");
+        assert_eq!(content, "two");
+        let (ids, _, _, _) = notes.get_notes_list();
+        assert_eq!(ids, vec![zero, last]);
+        
+        // The purged note is zeroed out
+        assert_eq!(NoteOwner::get(&sdk, middle), Address::default());
+        assert!(load_note_content(&sdk, &middle).is_empty());
+        assert!(NoteTitle::get(&sdk, middle).is_empty());
+        assert_eq!(NoteCreatedAt::get(&sdk, middle), U256::from(0));
+        assert_eq!(NoteUpdatedAt::get(&sdk, middle), U256::from(0));
+    }
+    
+    #[test]
+    fn test_purge_keeps_other_notes_metadata() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let reader = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        set_block_timestamp(&sdk, 100);
+        notes.create_note("Zero".to_string(), "zero".to_string());
+        let middle = notes.create_note("Middle".to_string(), "middle".to_string());
+        as_caller(&sdk, stranger).register_user(Bytes::from("stranger key".as_bytes().to_vec()));
+        as_caller(&sdk, reader).register_user(Bytes::from("reader key".as_bytes().to_vec()));
+        notes.share_note(middle, stranger);
+        notes.set_note_tags(middle, vec!["purged".to_string()]);
+        set_block_timestamp(&sdk, 200);
+        let last = notes.create_note("Last".to_string(), "last".to_string());
+        notes.set_note_tags(last, vec!["kept".to_string()]);
+        notes.pin_note(last);
+        notes.set_note_public(last, true);
+        notes.set_note_category(last, U256::from(7));
+        notes.archive_note(last);
+        notes.share_note(last, reader);
+        
+        // Purging the middle note leaves "Last" and all of its metadata untouched
+        set_block_timestamp(&sdk, 300);
+        notes.purge_note(middle);
+        let (title, content, created_at, updated_at) = notes.get_note(last);
+        assert_eq!(title, "Last");
+        assert_eq!(content, "last");
+        assert_eq!((created_at, updated_at), (U256::from(200), U256::from(200)));
+        assert_eq!(notes.get_note_tags(last), vec!["kept".to_string()]);
+        assert!(NotePinned::get(&sdk, last));
+        assert!(NotePublic::get(&sdk, last));
+        assert!(NoteArchived::get(&sdk, last));
+        assert_eq!(NoteCategory::get(&sdk, last), U256::from(7));
+        assert_eq!(notes.find_note_by_title("Last".to_string()), (true, last));
+        assert!(NoteSharedWith::get(&sdk, last, reader));
+        
+        // The purged note's tags and shares are gone
+        assert_eq!(NoteTagCount::get(&sdk, middle), U256::from(0));
+        assert!(!NoteSharedWith::get(&sdk, middle, stranger));
+        assert_eq!(NoteShareCount::get(&sdk, middle), U256::from(0));
+    }
+    
+    #[test]
+    fn test_long_note_spans_chunks() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let long: String = (0..MAX_CONTENT_LEN).map(|i| (b'a' + (i % 26) as u8) as char).collect();
+        let note_id = notes.create_note("Long".to_string(), long.clone());
+        
+        // Ciphertext is the plaintext plus owner prefix, nonce and tag, split into chunks
+        let stored_len = OWNER_PREFIX_LEN + NONCE_LEN + MAX_CONTENT_LEN + TAG_LEN;
+        assert_eq!(NoteChunkCount::get(&sdk, note_id), U256::from(stored_len.div_ceil(NOTE_CHUNK_SIZE)));
+        assert_eq!(notes.get_note(note_id).1, long);
+        
+        // Shrinking the note drops the chunks it no longer needs
+        notes.update_note(note_id, "Long".to_string(), "short now".to_string());
+        assert_eq!(NoteChunkCount::get(&sdk, note_id), U256::from(1));
+        assert!(NoteContentChunk::get(&sdk, note_id, U256::from(1)).is_empty());
+        assert_eq!(notes.get_note(note_id).1, "short now");
+        
+        // Growing it again and purging clears every chunk
+        notes.update_note(note_id, "Long".to_string(), long);
+        notes.purge_note(note_id);
+        assert_eq!(NoteChunkCount::get(&sdk, note_id), U256::from(0));
+        assert!(NoteContentChunk::get(&sdk, note_id, U256::from(0)).is_empty());
+        assert!(NoteContentChunk::get(&sdk, note_id, U256::from(8)).is_empty());
+    }
+    
+    #[test]
+    fn test_storage_used_matches_stored_content() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        // Sum the stored ciphertext the slow way
+        let brute_force = |user: Address| {
+            let mut total = 0usize;
+            for note_id in load_note_ids(&sdk, &user) {
+                total += load_note_content(&sdk, &note_id).len();
+            }
+            U256::from(total)
+        };
+        
+        let mut notes = as_caller(&sdk, owner);
+        let first = notes.create_note("First".to_string(), "short".to_string());
+        let second = notes.create_note("Second".to_string(), "a somewhat longer body".to_string());
+        notes.create_note("Third".to_string(), "third".to_string());
+        assert_eq!(notes.get_storage_used(owner), brute_force(owner));
+        
+        notes.update_note(first, "First".to_string(), "now much longer than before".to_string());
+        notes.update_note(second, "Second".to_string(), "tiny".to_string());
+        assert_eq!(notes.get_storage_used(owner), brute_force(owner));
+        
+        // Binned notes still take space until purged
+        notes.delete_note(first);
+        assert_eq!(notes.get_storage_used(owner), brute_force(owner));
+        notes.purge_note(first);
+        assert_eq!(notes.get_storage_used(owner), brute_force(owner));
+        
+        // A second user's notes are counted separately, including one handed over
+        let other = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        let mut other_notes = as_caller(&sdk, other);
+        other_notes.register_user(Bytes::from("other key".as_bytes().to_vec()));
+        other_notes.create_note("Other".to_string(), "someone else's note".to_string());
+        assert_eq!(other_notes.get_storage_used(other), brute_force(other));
+        notes.transfer_note(second, other);
+        assert_eq!(notes.get_storage_used(owner), brute_force(owner));
+        assert_eq!(notes.get_storage_used(other), brute_force(other));
+    }
+    
+    #[test]
+    fn test_note_sharing() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let reader = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Shared".to_string(), "shared content".to_string());
+        as_caller(&sdk, reader).register_user(Bytes::from("reader key".as_bytes().to_vec()));
+        notes.share_note(note_id, reader);
+        
+        // The grantee can read and decrypt the note
+        let notes = as_caller(&sdk, reader);
+        let (title, content, _, _) = notes.get_note(note_id);
+        assert_eq!(title, "Shared");
+        assert_eq!(content, "shared content");
+        
+        // Someone else cannot
+        let notes = as_caller(&sdk, stranger);
+        let (exists, title, _, _, _) = notes.try_get_note(note_id);
+        assert!(!exists);
+        assert_eq!(title, "");
+        
+        // Revoking removes access again
+        let mut notes = as_caller(&sdk, owner);
+        notes.revoke_share(note_id, reader);
+        let notes = as_caller(&sdk, reader);
+        let (exists, _, _, _, _) = notes.try_get_note(note_id);
+        assert!(!exists);
+    }
+    
+    #[test]
+    fn test_public_note_readable_by_stranger() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.register_user(Bytes::from("owner key".as_bytes().to_vec()));
+        let note_id = notes.create_note("Release 1.0".to_string(), "Now available".to_string());
+        notes.set_note_public(note_id, true);
+        
+        // Any caller can read it, decrypted with the owner's key
+        let notes = as_caller(&sdk, stranger);
+        let (title, content, _, _) = notes.get_note(note_id);
+        assert_eq!(title, "Release 1.0");
+        assert_eq!(content, "Now available");
+        let (title, content, _) = notes.get_public_note(owner, note_id);
+        assert_eq!(title, "Release 1.0");
+        assert_eq!(content, "Now available");
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note is not public")]
+    fn test_private_note_rejected_for_stranger() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Diary".to_string(), "private".to_string());
+        notes.set_note_public(note_id, true);
+        notes.set_note_public(note_id, false);
+        
+        let notes = as_caller(&sdk, stranger);
+        let (exists, _, _, _, _) = notes.try_get_note(note_id);
+        assert!(!exists);
+        notes.get_public_note(owner, note_id);
+    }
+    
+    #[test]
+    fn test_read_note_logged() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let reader = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        set_block_timestamp(&sdk, 100);
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Audit".to_string(), "sensitive".to_string());
+        as_caller(&sdk, reader).register_user(Bytes::from("reader key".as_bytes().to_vec()));
+        notes.share_note(note_id, reader);
+        take_event_logs(&sdk);
+        
+        // The owner's own reads aren't logged
+        assert_eq!(
+            notes.read_note_logged(note_id),
+            ("Audit".to_string(), "sensitive".to_string(), U256::from(100))
+        );
+        assert!(take_event_logs(&sdk).is_empty());
+        
+        let mut reader_notes = as_caller(&sdk, reader);
+        assert_eq!(
+            reader_notes.read_note_logged(note_id),
+            ("Audit".to_string(), "sensitive".to_string(), U256::from(100))
+        );
+        let logs = take_event_logs(&sdk);
+        assert_eq!(logs.len(), 1);
+        let (topics, data) = &logs[0];
+        assert_eq!(topics[0].as_slice(), EVENT_NOTE_ACCESSED);
+        assert_eq!(topics[1], address_topic(&reader));
+        assert_eq!(topics[2].as_slice(), note_id.to_be_bytes::<32>().as_slice());
+        assert!(data.is_empty());
+    }
+    
+    #[test]
+    #[should_panic(expected = "Contract is paused")]
+    fn test_read_note_logged_while_paused() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.deploy();
+        let note_id = notes.create_note("Audit".to_string(), "sensitive".to_string());
+        notes.pause();
+        
+        // The plain read still works, the logged one doesn't
+        assert_eq!(notes.get_note(note_id).1, "sensitive");
+        notes.read_note_logged(note_id);
+    }
+    
+    #[test]
+    fn test_get_note_public_for_shared_reader() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let reader = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Shared".to_string(), "for the reader".to_string());
+        as_caller(&sdk, reader).register_user(Bytes::from("reader key".as_bytes().to_vec()));
+        notes.share_note(note_id, reader);
+        
+        let notes = as_caller(&sdk, reader);
+        let (title, content, _) = notes.get_note_public(owner, note_id);
+        assert_eq!(title, "Shared");
+        assert_eq!(content, "for the reader");
+    }
+    
+    #[test]
+    fn test_get_note_ciphertext() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let reader = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let key = Bytes::from("owner key".as_bytes().to_vec());
+        notes.register_user(key.clone());
+        let note_id = notes.create_note("Offline".to_string(), "decrypt me locally".to_string());
+        as_caller(&sdk, reader).register_user(Bytes::from("reader key".as_bytes().to_vec()));
+        notes.share_note(note_id, reader);
+        
+        // Exactly what is stored, and it opens off-chain with the content key from the owner's envelope
+        let ciphertext = notes.get_note_ciphertext(note_id);
+        assert_eq!(ciphertext, load_note_content(&sdk, &note_id));
+        let envelope = notes.get_note_envelope(note_id);
+        assert_eq!(envelope, NoteEnvelope::get(&sdk, note_id, owner));
+        let wrapping_key = derive_cipher_key(owner.as_slice(), &key);
+        let content_key = aead_open(
+            CIPHER_AES_GCM,
+            &wrapping_key,
+            &envelope[32..32 + NONCE_LEN],
+            &envelope[32 + NONCE_LEN..],
+            &envelope_aad(&note_id, &owner),
+        )
+        .unwrap();
+        let plaintext = open_with_key(CIPHER_AES_GCM, content_key.as_slice().try_into().unwrap(), &ciphertext).unwrap();
+        assert_eq!(plaintext, b"decrypt me locally".to_vec());
+        
+        // The grantee gets the same ciphertext and an envelope of their own
+        let reader_notes = as_caller(&sdk, reader);
+        assert_eq!(reader_notes.get_note_ciphertext(note_id), ciphertext);
+        assert_eq!(reader_notes.get_note_envelope(note_id), NoteEnvelope::get(&sdk, note_id, reader));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Not authorized to read this note")]
+    fn test_get_note_ciphertext_rejects_stranger() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Private".to_string(), "not yours".to_string());
+        
+        as_caller(&sdk, stranger).get_note_ciphertext(note_id);
+    }
+    
+    #[test]
+    fn test_note_attachment_round_trip() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let recipient = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.register_user(Bytes::from("owner key".as_bytes().to_vec()));
+        let note_id = notes.create_note("Photo".to_string(), "see attachment".to_string());
+        assert!(notes.get_note_attachment(note_id).is_empty());
+        
+        // Arbitrary bytes, including ones that aren't valid UTF-8, are stored encrypted
+        let thumbnail = Bytes::from(vec![0x89, 0x50, 0x4e, 0x47, 0x00, 0xff, 0xfe, 0x10]);
+        notes.set_note_attachment(note_id, thumbnail.clone());
+        let stored = NoteAttachment::get(&sdk, note_id);
+        assert_eq!(stored.len(), OWNER_PREFIX_LEN + NONCE_LEN + thumbnail.len() + TAG_LEN);
+        assert_eq!(notes.get_note_attachment(note_id), thumbnail);
+        
+        // Still readable after a key rotation, and it follows the note on transfer
+        notes.rotate_encryption_key(Bytes::from("new owner key".as_bytes().to_vec()));
+        assert_ne!(NoteAttachment::get(&sdk, note_id), stored);
+        assert_eq!(notes.get_note_attachment(note_id), thumbnail);
+        as_caller(&sdk, recipient).register_user(Bytes::from("recipient key".as_bytes().to_vec()));
+        let moved = notes.transfer_note(note_id, recipient);
+        assert!(NoteAttachment::get(&sdk, note_id).is_empty());
+        assert_eq!(as_caller(&sdk, recipient).get_note_attachment(moved), thumbnail);
+        
+        // Empty data removes it
+        let mut recipient_notes = as_caller(&sdk, recipient);
+        recipient_notes.set_note_attachment(moved, Bytes::new());
+        assert!(recipient_notes.get_note_attachment(moved).is_empty());
+    }
+    
+    #[test]
+    #[should_panic(expected = "Attachment too large")]
+    fn test_note_attachment_too_large() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Photo".to_string(), "too big".to_string());
+        notes.set_note_attachment(note_id, Bytes::from(vec![0u8; MAX_ATTACHMENT_LEN + 1]));
+    }
+    
+    #[test]
+    fn test_note_attachment_cid() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let reader = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Slides".to_string(), "see attachment".to_string());
+        assert!(notes.get_note_attachment_cid(note_id).is_empty());
+        
+        let cid = Bytes::from("bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi".as_bytes().to_vec());
+        notes.set_note_attachment_cid(note_id, cid.clone());
+        assert_eq!(notes.get_note_attachment_cid(note_id), cid);
+        
+        // Readers of the note see the reference too
+        as_caller(&sdk, reader).register_user(Bytes::from("reader key".as_bytes().to_vec()));
+        notes.share_note(note_id, reader);
+        assert_eq!(as_caller(&sdk, reader).get_note_attachment_cid(note_id), cid);
+        
+        // Empty bytes clear it
+        notes.set_note_attachment_cid(note_id, Bytes::new());
+        assert!(notes.get_note_attachment_cid(note_id).is_empty());
+    }
+    
+    #[test]
+    #[should_panic(expected = "Attachment CID too long")]
+    fn test_note_attachment_cid_too_long() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Slides".to_string(), "content".to_string());
+        notes.set_note_attachment_cid(note_id, Bytes::from(vec![b'a'; MAX_ATTACHMENT_CID_LEN + 1]));
+    }
+    
+    #[test]
+    fn test_shared_note_envelope() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let reader = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.register_user(Bytes::from("owner key".as_bytes().to_vec()));
+        let note_id = notes.create_note("Shared".to_string(), "first draft".to_string());
+        let mut reader_notes = as_caller(&sdk, reader);
+        reader_notes.register_user(Bytes::from("reader key".as_bytes().to_vec()));
+        notes.share_note(note_id, reader);
+        
+        // The envelope holds the note's own content key, wrapped under the reader's key. It is
+        // neither the owner's key nor the key of any other note
+        let envelope = NoteEnvelope::get(&sdk, note_id, reader);
+        assert_eq!(envelope.len(), ENVELOPE_LEN);
+        assert_eq!(reader_notes.get_note_envelope(note_id), envelope);
+        let content_key = note_content_key(&sdk, &note_id);
+        assert_eq!(unwrap_note_key(&sdk, &note_id, &reader), Some(content_key));
+        assert_ne!(content_key, derive_cipher_key(owner.as_slice(), &UserEncryptionKeys::get(&sdk, owner)));
+        let other_id = notes.create_note("Private".to_string(), "not shared".to_string());
+        assert_ne!(note_content_key(&sdk, &other_id), content_key);
+        assert!(open_with_key(CIPHER_AES_GCM, &content_key, &load_note_content(&sdk, &other_id)).is_none());
+        assert_eq!(reader_notes.get_note_public(owner, note_id).1, "first draft");
+        
+        // Re-encrypting the content re-wraps the envelope, and the reader's own key
+        // rotation doesn't lock them out
+        notes.update_note(note_id, "Shared".to_string(), "second draft".to_string());
+        notes.rotate_encryption_key(Bytes::from("new owner key".as_bytes().to_vec()));
+        reader_notes.update_encryption_key(Bytes::from("new reader key".as_bytes().to_vec()));
+        assert_ne!(NoteEnvelope::get(&sdk, note_id, reader), envelope);
+        assert_eq!(reader_notes.get_note_public(owner, note_id).1, "second draft");
+        
+        // Without a valid envelope the reader can't decrypt, even with read access
+        let mut raw_sdk = sdk.clone();
+        let mut tampered = NoteEnvelope::get(&sdk, note_id, reader).to_vec();
+        tampered[ENVELOPE_LEN - 1] ^= 0xff;
+        NoteEnvelope::set(&mut raw_sdk, note_id, reader, Bytes::from(tampered));
+        assert_eq!(
+            reader_notes.get_note_public(owner, note_id).1,
+            "Error: You don't have permission to decrypt this note"
+        );
+        
+        // Revoking drops the envelope and moves the content to a new key
+        let shared_key = note_content_key(&sdk, &note_id);
+        notes.revoke_share(note_id, reader);
+        assert!(NoteEnvelope::get(&sdk, note_id, reader).is_empty());
+        assert!(open_with_key(CIPHER_AES_GCM, &shared_key, &load_note_content(&sdk, &note_id)).is_none());
+        assert_eq!(notes.get_note(note_id).1, "second draft");
+    }
+    
+    #[test]
+    fn test_get_shared_with_me() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let reader = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        let other_owner = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut reader_notes = as_caller(&sdk, reader);
+        reader_notes.register_user(Bytes::from("reader key".as_bytes().to_vec()));
+        let mut notes = as_caller(&sdk, owner);
+        let first = notes.create_note("First".to_string(), "one".to_string());
+        let second = notes.create_note("Second".to_string(), "two".to_string());
+        notes.create_note("Private".to_string(), "mine".to_string());
+        let mut other_notes = as_caller(&sdk, other_owner);
+        let third = other_notes.create_note("Third".to_string(), "three".to_string());
+        
+        notes.share_note(first, reader);
+        notes.share_note(second, reader);
+        notes.share_note(second, reader);
+        other_notes.share_note(third, reader);
+        assert_eq!(reader_notes.get_shared_with_me(), (vec![owner, owner, other_owner], vec![first, second, third]));
+        
+        // Revoking moves the last entry into the gap
+        notes.revoke_share(first, reader);
+        assert_eq!(reader_notes.get_shared_with_me(), (vec![other_owner, owner], vec![third, second]));
+        assert_eq!(SharedWithMeIndex::get(&sdk, first, reader), U256::from(0));
+        
+        // Binned notes are hidden, purged ones are dropped from the list
+        notes.delete_note(second);
+        assert_eq!(reader_notes.get_shared_with_me(), (vec![other_owner], vec![third]));
+        notes.purge_note(second);
+        assert_eq!(SharedWithMeCount::get(&sdk, reader), U256::from(1));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Share recipient has no encryption key")]
+    fn test_share_with_unregistered_recipient() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Shared".to_string(), "content".to_string());
+        notes.share_note(note_id, stranger);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Share recipient has no encryption key")]
+    fn test_share_with_keyless_recipient() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        // Registered, even with a note of their own, but no key set
+        let mut stranger_notes = as_caller(&sdk, stranger);
+        stranger_notes.create_note("Mine".to_string(), "content".to_string());
+        assert!(stranger_notes.is_registered(stranger));
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Shared".to_string(), "content".to_string());
+        notes.share_note(note_id, stranger);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Not authorized to read this note")]
+    fn test_get_note_public_rejects_unshared_reader() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Private".to_string(), "not shared".to_string());
+        
+        let notes = as_caller(&sdk, stranger);
+        notes.get_note_public(owner, note_id);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Only the note owner can share it")]
+    fn test_share_requires_owner() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Private".to_string(), "content".to_string());
+        
+        let mut notes = as_caller(&sdk, stranger);
+        notes.share_note(note_id, stranger);
+    }
+    
+    #[test]
+    fn test_decrypt_permission_for_non_shared_user() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.register_user(Bytes::from("owner secret".as_bytes().to_vec()));
+        let ciphertext = notes.encrypt_note("owner only".to_string());
+        
+        // A user without access gets the permission error for the owner's ciphertext
+        let notes = as_caller(&sdk, stranger);
+        assert_eq!(
+            notes.decrypt_note(ciphertext),
+            "Error: You don't have permission to decrypt this note"
+        );
+    }
+    
+    #[test]
+    fn test_stored_note_tamper_detection() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Tamper".to_string(), "original content".to_string());
+        let (_, content, _, _) = notes.get_note(note_id);
+        assert_eq!(content, "original content");
+        
+        // Corrupt one byte of the stored ciphertext body
+        let mut stored = load_note_content(&sdk, &note_id).to_vec();
+        stored[OWNER_PREFIX_LEN + NONCE_LEN] ^= 0x80;
+        let mut raw_sdk = sdk.clone();
+        store_note_content(&mut raw_sdk, &note_id, &Bytes::from(stored.clone()));
+        let (_, content, _, _) = notes.get_note(note_id);
+        assert_eq!(content, "Error: authentication failed");
+        
+        // Corrupting the nonce is caught the same way
+        stored[OWNER_PREFIX_LEN + NONCE_LEN] ^= 0x80;
+        stored[OWNER_PREFIX_LEN] ^= 0x01;
+        store_note_content(&mut raw_sdk, &note_id, &Bytes::from(stored));
+        let (_, content, _, _) = notes.get_note(note_id);
+        assert_eq!(content, "Error: authentication failed");
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note does not exist")]
+    fn test_get_note_reverts_for_missing_note() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let notes = as_caller(&sdk, owner);
+        let (exists, _, _, _, _) = notes.try_get_note(U256::from(7));
+        assert!(!exists);
+        
+        notes.get_note(U256::from(7));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Not authorized to read this note")]
+    fn test_get_note_reverts_for_foreign_note() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Mine".to_string(), "content".to_string());
+        
+        let notes = as_caller(&sdk, stranger);
+        let (exists, _, _, _, _) = notes.try_get_note(note_id);
+        assert!(!exists);
+        
+        notes.get_note(note_id);
+    }
+    
+    #[test]
+    fn test_notes_list_pages_large_accounts() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        for i in 0..150 {
+            notes.create_note(format!("Note {}", i), "content".to_string());
+        }
+        assert_eq!(notes.get_note_count(), U256::from(150));
+        
+        // First page is capped at the page size
+        let (ids, titles, _, _, _) = notes.get_notes_page(U256::from(0), U256::from(500));
+        assert_eq!(ids.len(), 100);
+        assert_eq!(titles[99], "Note 99");
+        
+        // Second page holds the remainder
+        let (ids, titles, _, _, _) = notes.get_notes_page(U256::from(100), U256::from(100));
+        assert_eq!(ids.len(), 50);
+        assert_eq!(ids[0], U256::from(100));
+        assert_eq!(titles[49], "Note 149");
+        
+        // The legacy listing returns the first page
+        let (ids, _, _, _) = notes.get_notes_list();
+        assert_eq!(ids.len(), 100);
+        assert_eq!(ids[0], U256::from(0));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note count out of range")]
+    fn test_oversized_note_count_reverts() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let notes = as_caller(&sdk, owner);
+        let mut raw_sdk = sdk.clone();
+        UserNotesCount::set(&mut raw_sdk, owner, U256::MAX);
+        
+        notes.get_notes_list();
+    }
+    
+    #[test]
+    fn test_input_limits_accept_exact_size() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        assert_eq!(notes.get_limits(), (U256::from(MAX_TITLE_LEN), U256::from(MAX_CONTENT_LEN)));
+        
+        let title = "t".repeat(MAX_TITLE_LEN);
+        let content = "c".repeat(MAX_CONTENT_LEN);
+        let note_id = notes.create_note(title.clone(), content.clone());
+        notes.update_note(note_id, title.clone(), content.clone());
+        
+        let (stored_title, stored_content, _, _) = notes.get_note(note_id);
+        assert_eq!(stored_title, title);
+        assert_eq!(stored_content, content);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Title exceeds maximum length")]
+    fn test_title_over_limit_reverts() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.create_note("t".repeat(MAX_TITLE_LEN + 1), "content".to_string());
+    }
+    
+    #[test]
+    #[should_panic(expected = "Title exceeds maximum length")]
+    fn test_title_over_limit_reverts_on_rename() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Title".to_string(), "content".to_string());
+        notes.update_note_title(note_id, "t".repeat(MAX_TITLE_LEN + 1));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Content exceeds maximum length")]
+    fn test_content_over_limit_reverts_on_update() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Title".to_string(), "content".to_string());
+        notes.update_note(note_id, "Title".to_string(), "c".repeat(MAX_CONTENT_LEN + 1));
+    }
+    
+    #[test]
+    fn test_rotate_encryption_key() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.register_user(Bytes::from("old key".as_bytes().to_vec()));
+        let first = notes.create_note("First".to_string(), "first draft".to_string());
+        notes.update_note(first, "First".to_string(), "first content".to_string());
+        let second = notes.create_note("Second".to_string(), "second content".to_string());
+        let draft = NoteVersionContent::get(&sdk, first, U256::from(0));
+        
+        let new_key = Bytes::from("new key".as_bytes().to_vec());
+        notes.rotate_encryption_key(new_key.clone());
+        
+        // Notes were re-encrypted, so they still read back as plaintext under the new key
+        assert_eq!(UserEncryptionKeys::get(&sdk, owner), new_key);
+        assert!(PendingEncryptionKeys::get(&sdk, owner).is_empty());
+        let (_, content, _, _) = notes.get_note(first);
+        assert_eq!(content, "first content");
+        let (_, content, _, _) = notes.get_note(second);
+        assert_eq!(content, "second content");
+        
+        // So was the history, which still restores
+        assert_ne!(NoteVersionContent::get(&sdk, first, U256::from(0)), draft);
+        assert_eq!(notes.get_note_version(first, U256::from(0)), "first draft");
+        notes.revert_note_to_version(first, U256::from(0));
+        assert_eq!(notes.get_note(first).1, "first draft");
+    }
+    
+    #[test]
+    fn test_rotate_encryption_key_in_ranges() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.register_user(Bytes::from("old key".as_bytes().to_vec()));
+        for i in 0..3 {
+            notes.create_note(format!("Note {}", i), format!("content {}", i));
+        }
+        
+        // First batch migrates two notes; the old key stays committed until the end
+        let new_key = Bytes::from("new key".as_bytes().to_vec());
+        let remaining = notes.rotate_encryption_key_range(new_key.clone(), U256::from(2));
+        assert_eq!(remaining, U256::from(1));
+        assert_eq!(UserEncryptionKeys::get(&sdk, owner), Bytes::from("old key".as_bytes().to_vec()));
+        
+        // Migrated and not-yet-migrated notes are both readable mid-rotation
+        for i in 0..3 {
+            let (_, content, _, _) = notes.get_note(U256::from(i));
+            assert_eq!(content, format!("content {}", i));
+        }
+        
+        // Finishing the rotation commits the key
+        let remaining = notes.rotate_encryption_key_range(new_key.clone(), U256::from(2));
+        assert_eq!(remaining, U256::from(0));
+        assert_eq!(UserEncryptionKeys::get(&sdk, owner), new_key);
+        for i in 0..3 {
+            let (_, content, _, _) = notes.get_note(U256::from(i));
+            assert_eq!(content, format!("content {}", i));
+        }
+    }
+    
+    #[test]
+    #[should_panic(expected = "Key rotation in progress")]
+    fn test_register_user_during_rotation_reverts() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.register_user(Bytes::from("old key".as_bytes().to_vec()));
+        notes.create_note("First".to_string(), "first content".to_string());
+        notes.create_note("Second".to_string(), "second content".to_string());
+        
+        // The first note is already stamped with the pending key's version
+        notes.rotate_encryption_key_range(Bytes::from("new key".as_bytes().to_vec()), U256::from(1));
+        notes.register_user(Bytes::from("other key".as_bytes().to_vec()));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Encryption key not set")]
+    fn test_strict_mode_requires_key() {
+        let admin = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let user = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, admin);
+        notes.deploy();
+        notes.set_require_encryption_key(true);
+        assert!(notes.require_encryption_key());
+        
+        let mut notes = as_caller(&sdk, user);
+        notes.create_note("Weak".to_string(), "content".to_string());
+    }
+    
+    #[test]
+    fn test_strict_mode_with_key() {
+        let admin = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let user = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        // A note written under the fallback before strict mode stays readable
+        let mut user_notes = as_caller(&sdk, user);
+        let old = user_notes.create_note("Old".to_string(), "old".to_string());
+        
+        let mut notes = as_caller(&sdk, admin);
+        notes.deploy();
+        notes.set_require_encryption_key(true);
+        
+        user_notes.register_user(Bytes::from("user key".as_bytes().to_vec()));
+        let new = user_notes.create_note("New".to_string(), "new".to_string());
+        assert_eq!(user_notes.get_note(old).1, "old");
+        assert_eq!(user_notes.get_note(new).1, "new");
+    }
+    
+    #[test]
+    fn test_cipher_mode_per_note() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.register_user(Bytes::from("owner key".as_bytes().to_vec()));
+        assert_eq!(notes.get_cipher_mode(owner), CIPHER_AES_GCM);
+        let aes_note = notes.create_note("AES".to_string(), "under aes".to_string());
+        
+        notes.set_cipher_mode(CIPHER_CHACHA20_POLY1305);
+        assert_eq!(notes.get_cipher_mode(owner), CIPHER_CHACHA20_POLY1305);
+        let chacha_note = notes.create_note("ChaCha".to_string(), "under chacha".to_string());
+        assert_eq!(NoteCipherMode::get(&sdk, aes_note), U256::from(CIPHER_AES_GCM));
+        assert_eq!(NoteCipherMode::get(&sdk, chacha_note), U256::from(CIPHER_CHACHA20_POLY1305));
+        
+        // Each note opens with the cipher it was written with, whatever the current mode
+        assert_eq!(notes.get_note(aes_note).1, "under aes");
+        assert_eq!(notes.get_note(chacha_note).1, "under chacha");
+        notes.set_cipher_mode(CIPHER_AES_GCM);
+        assert_eq!(notes.get_note(aes_note).1, "under aes");
+        assert_eq!(notes.get_note(chacha_note).1, "under chacha");
+        
+        // An update re-encrypts under the current mode; the old content stays readable as a version
+        notes.update_note(chacha_note, "ChaCha".to_string(), "now aes".to_string());
+        assert_eq!(NoteCipherMode::get(&sdk, chacha_note), U256::from(CIPHER_AES_GCM));
+        assert_eq!(notes.get_note(chacha_note).1, "now aes");
+        assert_eq!(notes.get_note_version(chacha_note, U256::from(0)), "under chacha");
+    }
+    
+    #[test]
+    fn test_encrypt_note_follows_cipher_mode() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.register_user(Bytes::from("owner key".as_bytes().to_vec()));
+        notes.set_cipher_mode(CIPHER_CHACHA20_POLY1305);
+        let ciphertext = notes.encrypt_note("raw".to_string());
+        assert_eq!(notes.decrypt_note(ciphertext.clone()), "raw");
+        
+        // Same key, different cipher: the tag doesn't verify
+        notes.set_cipher_mode(CIPHER_AES_GCM);
+        assert_eq!(notes.try_decrypt_note(ciphertext), (DecryptError::AuthenticationFailed.code(), String::new()));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Unknown cipher mode")]
+    fn test_unknown_cipher_mode() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.set_cipher_mode(2);
+    }
+    
+    #[test]
+    fn test_export_encryption_key() {
+        let user = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, user);
+        let key = Bytes::from("user key".as_bytes().to_vec());
+        notes.register_user(key.clone());
+        assert_eq!(notes.export_encryption_key(), key);
+        
+        let new_key = Bytes::from("new user key".as_bytes().to_vec());
+        notes.update_encryption_key(new_key.clone());
+        assert_eq!(notes.export_encryption_key(), new_key);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Encryption key not set")]
+    fn test_export_encryption_key_without_key() {
+        let user = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, user);
+        notes.register_user(Bytes::new());
+        notes.export_encryption_key();
+    }
+    
+    #[test]
+    fn test_get_user_info() {
+        let user = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        let fresh = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, user);
+        assert_eq!(notes.get_user_info(user), (false, U256::from(0), B256::ZERO));
+        
+        // An explicit empty-key registration is told apart from a fresh address
+        notes.register_user(Bytes::new());
+        assert_eq!(notes.get_user_info(user), (true, U256::from(0), B256::ZERO));
+        assert_eq!(notes.get_user_info(fresh), (false, U256::from(0), B256::ZERO));
+        
+        let key = Bytes::from("user key".as_bytes().to_vec());
+        notes.register_user(key.clone());
+        notes.create_note("Note".to_string(), "content".to_string());
+        assert_eq!(notes.get_user_info(user), (true, U256::from(1), keccak256(&key)));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Title contains control characters")]
+    fn test_null_byte_in_title_rejected() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.create_note("Bad\0title".to_string(), "content".to_string());
+    }
+    
+    #[test]
+    #[should_panic(expected = "Title contains control characters")]
+    fn test_null_byte_in_renamed_title_rejected() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Good title".to_string(), "content".to_string());
+        notes.update_note(note_id, "Bad\0title".to_string(), "content".to_string());
+    }
+    
+    #[test]
+    fn test_control_chars_opt_out() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        
+        // Tab and newline are always fine
+        let note_id = notes.create_note("Line one\n\tLine two".to_string(), "content".to_string());
+        assert_eq!(notes.get_note(note_id).0, "Line one\n\tLine two");
+        
+        notes.set_allow_control_chars(true);
+        assert!(notes.allows_control_chars(owner));
+        notes.update_note_title(note_id, "Raw\0title".to_string());
+        assert_eq!(notes.get_note(note_id).0, "Raw\0title");
+    }
+    
+    #[test]
+    fn test_get_user_profile() {
+        let user = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, user);
+        assert_eq!(notes.get_user_profile(), (U256::from(0), U256::from(0), false));
+        
+        notes.register_user(Bytes::from("user key".as_bytes().to_vec()));
+        let first = notes.create_note("First".to_string(), "first".to_string());
+        notes.create_note("Second".to_string(), "second".to_string());
+        notes.create_note("Third".to_string(), "third".to_string());
+        notes.delete_note(first);
+        notes.purge_note(first);
+        
+        // The purged note still counts towards the lifetime total
+        assert_eq!(notes.get_user_profile(), (U256::from(2), U256::from(3), true));
+    }
+    
+    #[test]
+    fn test_auto_register_once_without_key() {
+        let user = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, user);
+        notes.create_note("First".to_string(), "first".to_string());
+        notes.create_note("Second".to_string(), "second".to_string());
+        
+        // Registered on first use only, and no key was set along the way
+        let registrations = take_event_logs(&sdk)
+            .into_iter()
+            .filter(|(topics, _)| topics[0].as_slice() == EVENT_USER_REGISTERED)
+            .count();
+        assert_eq!(registrations, 1);
+        assert!(!notes.has_encryption_key(user));
+    }
+    
+    #[test]
+    fn test_register_user_emits_once() {
+        let user = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, user);
+        assert!(!notes.is_registered(user));
+        notes.register_user(Bytes::from("first key".as_bytes().to_vec()));
+        notes.register_user(Bytes::from("second key".as_bytes().to_vec()));
+        assert!(notes.is_registered(user));
+        
+        // The second call only replaced the key
+        let registrations = take_event_logs(&sdk)
+            .into_iter()
+            .filter(|(topics, _)| topics[0].as_slice() == EVENT_USER_REGISTERED)
+            .count();
+        assert_eq!(registrations, 1);
+        assert_eq!(notes.total_users(), U256::from(1));
+        assert_eq!(notes.export_encryption_key(), Bytes::from("second key".as_bytes().to_vec()));
+    }
+    
+    #[test]
+    fn test_key_updated_event() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let key_updates = |sdk: &TestingContext| -> Vec<(Vec<fluentbase_sdk::B256>, Bytes)> {
+            take_event_logs(sdk)
+                .into_iter()
+                .filter(|(topics, _)| topics[0].as_slice() == EVENT_KEY_UPDATED)
+                .collect()
+        };
+        
+        // Registering without a key keeps the address fallback and logs no key change
+        let mut notes = as_caller(&sdk, owner);
+        notes.register_user(Bytes::new());
+        assert!(key_updates(&sdk).is_empty());
+        assert!(!notes.has_encryption_key(owner));
+        
+        // Registering with a key logs its hash, never the key itself
+        let key = Bytes::from("first key".as_bytes().to_vec());
+        notes.register_user(key.clone());
+        let logs = key_updates(&sdk);
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].0[1], address_topic(&owner));
+        assert_eq!(logs[0].1.as_ref(), keccak256(&key).as_slice());
+        assert!(notes.has_encryption_key(owner));
+        
+        let new_key = Bytes::from("second key".as_bytes().to_vec());
+        notes.update_encryption_key(new_key.clone());
+        let logs = key_updates(&sdk);
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].1.as_ref(), keccak256(&new_key).as_slice());
+        
+        // A finished rotation logs the fingerprint once, when the new key takes over
+        notes.create_note("Note".to_string(), "content".to_string());
+        notes.create_note("Other".to_string(), "content".to_string());
+        let rotated_key = Bytes::from("rotated key".as_bytes().to_vec());
+        notes.rotate_encryption_key_range(rotated_key.clone(), U256::from(1));
+        assert!(key_updates(&sdk).is_empty());
+        notes.rotate_encryption_key_range(rotated_key.clone(), U256::from(1));
+        let logs = key_updates(&sdk);
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].0[1], address_topic(&owner));
+        assert_eq!(logs[0].1.as_ref(), keccak256(&rotated_key).as_slice());
+        
+        // The raw key never shows up in the log data
+        assert_ne!(logs[0].1.as_ref(), rotated_key.as_ref());
+    }
+    
+    #[test]
+    fn test_update_encryption_key_keeps_old_notes_readable() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.register_user(Bytes::from("old key".as_bytes().to_vec()));
+        let old_note = notes.create_note("Old".to_string(), "old content".to_string());
+        
+        // Swap the key without migrating, then write a note under the new one
+        notes.update_encryption_key(Bytes::from("new key".as_bytes().to_vec()));
+        let new_note = notes.create_note("New".to_string(), "new content".to_string());
+        assert_eq!(NotePerKeyId::get(&sdk, old_note), U256::from(1));
+        assert_eq!(NotePerKeyId::get(&sdk, new_note), U256::from(2));
+        
+        let (_, content, _, _) = notes.get_note(old_note);
+        assert_eq!(content, "old content");
+        let (_, content, _, _) = notes.get_note(new_note);
+        assert_eq!(content, "new content");
+    }
+    
+    #[test]
+    fn test_archive_and_unarchive() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let active = notes.create_note("Active".to_string(), "active".to_string());
+        let old = notes.create_note("Old".to_string(), "old".to_string());
+        
+        // Archived notes drop out of the listing but are still counted and readable
+        notes.archive_note(old);
+        let (ids, _, _, _) = notes.get_notes_list();
+        assert_eq!(ids, vec![active]);
+        assert_eq!(notes.get_note_count(), U256::from(2));
+        let (ids, titles, _) = notes.get_archived_notes();
+        assert_eq!(ids, vec![old]);
+        assert_eq!(titles, vec!["Old".to_string()]);
+        let (_, content, _, _) = notes.get_note(old);
+        assert_eq!(content, "old");
+        
+        // Unarchiving brings it back into the listing
+        notes.unarchive_note(old);
+        let (ids, _, _, _) = notes.get_notes_list();
+        assert_eq!(ids, vec![active, old]);
+        let (ids, _, _) = notes.get_archived_notes();
+        assert!(ids.is_empty());
+    }
+    
+    #[test]
+    fn test_soft_delete_and_restore() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.create_note("Keep".to_string(), "keep content".to_string());
+        let binned = notes.create_note("Binned".to_string(), "binned content".to_string());
+        
+        // Delete hides the note but keeps ids and the count stable
+        notes.delete_note(binned);
+        assert_eq!(notes.get_note_count(), U256::from(2));
+        let (exists, _, _, _, _) = notes.try_get_note(binned);
+        assert!(!exists);
+        let (ids, _, _, _) = notes.get_notes_list();
+        assert_eq!(ids, vec![U256::from(0)]);
+        let (ids, titles, _, _) = notes.get_deleted_notes();
+        assert_eq!(ids, vec![binned]);
+        assert_eq!(titles, vec!["Binned".to_string()]);
+        
+        // Restoring brings it back with its content intact
+        notes.restore_note(binned);
+        let (title, content, _, _) = notes.get_note(binned);
+        assert_eq!(title, "Binned");
+        assert_eq!(content, "binned content");
+        let (ids, _, _, _) = notes.get_deleted_notes();
+        assert!(ids.is_empty());
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note does not exist")]
+    fn test_purge_other_users_note_reverts() {
+        let alice = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let bob = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut bob_notes = as_caller(&sdk, bob);
+        let bob_id = bob_notes.create_note("Bob".to_string(), "bob content".to_string());
+        
+        as_caller(&sdk, alice).purge_note(bob_id);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note does not exist")]
+    fn test_purge_missing_note_reverts() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        as_caller(&sdk, owner).purge_note(U256::from(99));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Encryption key cannot be empty")]
+    fn test_empty_encryption_key_reverts() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.create_note("Title".to_string(), "content".to_string());
+        notes.update_encryption_key(Bytes::new());
+    }
+    
+    #[test]
+    #[should_panic(expected = "Encryption key cannot be empty")]
+    fn test_derive_cipher_key_rejects_empty_material() {
+        derive_cipher_key(&[], &Bytes::new());
+    }
+    
+    #[test]
+    fn test_constant_time_eq() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let mut last_byte_differs = owner.to_vec();
+        last_byte_differs[19] ^= 0x01;
+        
+        assert!(constant_time_eq(owner.as_slice(), owner.to_vec().as_slice()));
+        assert!(!constant_time_eq(owner.as_slice(), &last_byte_differs));
+        assert!(!constant_time_eq(owner.as_slice(), &owner.as_slice()[0..19]));
+    }
+    
+    #[test]
+    fn test_open_with_empty_key_material_fails_cleanly() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let ciphertext = notes.encrypt_note("secret".to_string());
+        
+        // No stored key and no address to fall back to: decrypting gives None, not a panic
+        assert_eq!(try_derive_cipher_key(&[], &Bytes::new()), None);
+        assert_eq!(open_with_user_key(CIPHER_AES_GCM, &[], &Bytes::new(), &ciphertext), None);
+    }
+    
+    #[test]
+    fn test_empty_note_distinguishable_from_missing() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note(String::new(), String::new());
+        
+        // An empty note exists, with empty title and content
+        let (exists, title, content, _, _) = notes.try_get_note(note_id);
+        assert!(exists);
+        assert_eq!(title, "");
+        assert_eq!(content, "");
+        
+        // A missing note reports the same empty fields, but the flag says it isn't there
+        let (exists, title, content, created_at, updated_at) = notes.try_get_note(note_id + U256::from(1));
+        assert!(!exists);
+        assert_eq!(title, "");
+        assert_eq!(content, "");
+        assert_eq!(created_at, U256::from(0));
+        assert_eq!(updated_at, U256::from(0));
+    }
+    
+    #[test]
+    fn test_find_note_by_title() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let first = notes.create_note("Groceries".to_string(), "milk".to_string());
+        let second = notes.create_note("Todo".to_string(), "write tests".to_string());
+        assert_eq!(notes.find_note_by_title("Groceries".to_string()), (true, first));
+        assert_eq!(notes.find_note_by_title("Todo".to_string()), (true, second));
+        assert_eq!(notes.find_note_by_title("Missing".to_string()), (false, U256::from(0)));
+        
+        // Renaming moves the index entry
+        notes.update_note(first, "Shopping".to_string(), "milk".to_string());
+        assert_eq!(notes.find_note_by_title("Groceries".to_string()), (false, U256::from(0)));
+        assert_eq!(notes.find_note_by_title("Shopping".to_string()), (true, first));
+        
+        // Purging the first note drops its entry; "Todo" keeps its id
+        notes.purge_note(first);
+        assert_eq!(notes.find_note_by_title("Shopping".to_string()), (false, U256::from(0)));
+        assert_eq!(notes.find_note_by_title("Todo".to_string()), (true, second));
+        
+        // Duplicate titles: the latest note wins
+        let duplicate = notes.create_note("Todo".to_string(), "again".to_string());
+        assert_eq!(notes.find_note_by_title("Todo".to_string()), (true, duplicate));
+    }
+    
+    #[test]
+    fn test_note_tags() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let work = notes.create_note("Work".to_string(), "work".to_string());
+        let home = notes.create_note("Home".to_string(), "home".to_string());
+        
+        // Adding tags
+        notes.set_note_tags(work, vec!["work".to_string(), "urgent".to_string()]);
+        notes.set_note_tags(home, vec!["personal".to_string(), "urgent".to_string()]);
+        assert_eq!(notes.get_note_tags(work), vec!["work".to_string(), "urgent".to_string()]);
+        assert_eq!(notes.get_notes_by_tag("urgent".to_string()), vec![work, home]);
+        
+        // Replacing with a shorter list drops the old tags
+        notes.set_note_tags(work, vec!["archive".to_string()]);
+        assert_eq!(notes.get_note_tags(work), vec!["archive".to_string()]);
+        assert_eq!(notes.get_notes_by_tag("urgent".to_string()), vec![home]);
+        
+        // Filtering by a tag no note has
+        assert!(notes.get_notes_by_tag("nothing".to_string()).is_empty());
+        
+        // Purging one note clears its tags and leaves the others alone
+        notes.purge_note(work);
+        assert_eq!(NoteTagCount::get(&sdk, work), U256::from(0));
+        assert_eq!(notes.get_note_tags(home), vec!["personal".to_string(), "urgent".to_string()]);
+        assert_eq!(notes.get_notes_by_tag("urgent".to_string()), vec![home]);
+    }
+    
+    #[test]
+    fn test_count_notes_by_tag() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let work = notes.create_note("Work".to_string(), "work".to_string());
+        let home = notes.create_note("Home".to_string(), "home".to_string());
+        let binned = notes.create_note("Binned".to_string(), "binned".to_string());
+        notes.set_note_tags(work, vec!["urgent".to_string()]);
+        notes.set_note_tags(home, vec!["personal".to_string(), "urgent".to_string()]);
+        notes.set_note_tags(binned, vec!["urgent".to_string()]);
+        notes.delete_note(binned);
+        
+        for tag in ["urgent", "personal", "nothing"] {
+            let ids = notes.get_notes_by_tag(tag.to_string());
+            assert_eq!(notes.count_notes_by_tag(tag.to_string()), U256::from(ids.len()));
+        }
+        assert_eq!(notes.count_notes_by_tag("urgent".to_string()), U256::from(2));
+    }
+    
+    #[test]
+    fn test_pin_survives_purge_of_other_note() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let first = notes.create_note("First".to_string(), "first".to_string());
+        notes.create_note("Second".to_string(), "second".to_string());
+        let third = notes.create_note("Third".to_string(), "third".to_string());
+        notes.pin_note(third);
+        
+        let (ids, _, _, _, pinned) = notes.get_notes_page(U256::from(0), U256::from(10));
+        assert_eq!(ids, vec![first, U256::from(1), third]);
+        assert_eq!(pinned, vec![false, false, true]);
+        
+        // Purging the first note moves "Third" into its list slot; the pin stays on its id
+        notes.purge_note(first);
+        let (ids, titles, _, _, pinned) = notes.get_notes_page(U256::from(0), U256::from(10));
+        assert_eq!(ids, vec![third, U256::from(1)]);
+        assert_eq!(titles, vec!["Third".to_string(), "Second".to_string()]);
+        assert_eq!(pinned, vec![true, false]);
+        assert!(NotePinned::get(&sdk, third));
+        
+        notes.unpin_note(third);
+        let (_, _, _, _, pinned) = notes.get_notes_page(U256::from(0), U256::from(10));
+        assert_eq!(pinned, vec![false, false]);
+    }
+    
+    #[test]
+    fn test_pinned_notes_listed_first() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        for i in 0..5 {
+            notes.create_note(format!("Note {}", i), "content".to_string());
+        }
+        notes.pin_note(U256::from(3));
+        notes.pin_note(U256::from(1));
+        
+        // Pinned notes come first, both groups stay in list order
+        let (ids, _, _, _) = notes.get_notes_list();
+        let expected: Vec<U256> = [1, 3, 0, 2, 4].iter().map(|i| U256::from(*i)).collect();
+        assert_eq!(ids, expected);
+        let (ids, titles, _, _) = notes.get_pinned_notes();
+        assert_eq!(ids, vec![U256::from(1), U256::from(3)]);
+        assert_eq!(titles, vec!["Note 1".to_string(), "Note 3".to_string()]);
+        
+        // Purging note 0 leaves the others in their order
+        notes.purge_note(U256::from(0));
+        let (ids, titles, _, _) = notes.get_notes_list();
+        assert_eq!(ids, vec![U256::from(1), U256::from(3), U256::from(2), U256::from(4)]);
+        assert_eq!(titles, vec!["Note 1".to_string(), "Note 3".to_string(), "Note 2".to_string(), "Note 4".to_string()]);
+        
+        // Purging a pinned note drops it from the pinned list
+        notes.purge_note(U256::from(1));
+        let (_, titles, _, _) = notes.get_pinned_notes();
+        assert_eq!(titles, vec!["Note 3".to_string()]);
+    }
+    
+    #[test]
+    fn test_notes_list_full_flags() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let plain = notes.create_note("Plain".to_string(), "plain".to_string());
+        let archived = notes.create_note("Archived".to_string(), "archived".to_string());
+        let pinned = notes.create_note("Pinned".to_string(), "pinned".to_string());
+        let binned = notes.create_note("Binned".to_string(), "binned".to_string());
+        notes.archive_note(archived);
+        notes.pin_note(pinned);
+        notes.delete_note(binned);
+        
+        // Archived notes are listed with their flag, binned ones are not listed
+        let (ids, titles, _, pinned_flags, archived_flags, _) = notes.get_notes_list_full();
+        assert_eq!(ids, vec![pinned, plain, archived]);
+        assert_eq!(titles, vec!["Pinned".to_string(), "Plain".to_string(), "Archived".to_string()]);
+        assert_eq!(pinned_flags, vec![true, false, false]);
+        assert_eq!(archived_flags, vec![false, false, true]);
+        
+        // The compatible listing still hides the archived note
+        let (ids, _, _, _) = notes.get_notes_list();
+        assert_eq!(ids, vec![pinned, plain]);
+    }
+    
+    #[test]
+    fn test_note_color() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let first = notes.create_note("First".to_string(), "first".to_string());
+        let second = notes.create_note("Second".to_string(), "second".to_string());
+        let last = notes.create_note("Last".to_string(), "last".to_string());
+        notes.set_note_color(last, U256::from(0x3366CCu64));
+        
+        let (ids, _, _, _, _, colors) = notes.get_notes_list_full();
+        assert_eq!(ids, vec![first, second, last]);
+        assert_eq!(colors, vec![U256::from(0), U256::from(0), U256::from(0x3366CCu64)]);
+        
+        // Purging the first note moves the last one into its list slot; the color stays with it
+        notes.purge_note(first);
+        assert_eq!(NoteIndex::get(&sdk, last), U256::from(0));
+        let (ids, _, _, _, _, colors) = notes.get_notes_list_full();
+        assert_eq!(ids, vec![second, last]);
+        assert_eq!(colors, vec![U256::from(0), U256::from(0x3366CCu64)]);
+        assert_eq!(NoteColor::get(&sdk, first), U256::from(0));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Invalid color")]
+    fn test_note_color_out_of_range() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Note".to_string(), "content".to_string());
+        notes.set_note_color(note_id, U256::from(0x1000000u64));
+    }
+    
+    #[test]
+    fn test_set_notes_order() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let first = notes.create_note("First".to_string(), "first".to_string());
+        let second = notes.create_note("Second".to_string(), "second".to_string());
+        let third = notes.create_note("Third".to_string(), "third".to_string());
+        
+        notes.set_notes_order(vec![third, first, second]);
+        let (ids, titles, _, _) = notes.get_notes_list();
+        assert_eq!(ids, vec![third, first, second]);
+        assert_eq!(titles, vec!["Third".to_string(), "First".to_string(), "Second".to_string()]);
+        
+        // New notes go to the end
+        let fourth = notes.create_note("Fourth".to_string(), "fourth".to_string());
+        let (ids, _, _, _) = notes.get_notes_list();
+        assert_eq!(ids, vec![third, first, second, fourth]);
+        
+        // Pinned notes still come first
+        notes.pin_note(second);
+        let (ids, _, _, _) = notes.get_notes_list();
+        assert_eq!(ids, vec![second, third, first, fourth]);
+    }
+    
+    #[test]
+    fn test_reorder_note() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let mut ids = Vec::new();
+        for i in 0..5 {
+            ids.push(notes.create_note(format!("Note {}", i), format!("content {}", i)));
+        }
+        
+        // Move the last note to the front, then the new front to the middle
+        notes.reorder_note(ids[4], U256::from(0));
+        let (listed, _, _, _) = notes.get_notes_list();
+        assert_eq!(listed, vec![ids[4], ids[0], ids[1], ids[2], ids[3]]);
+        
+        notes.reorder_note(ids[4], U256::from(2));
+        let (listed, titles, _, _) = notes.get_notes_list();
+        assert_eq!(listed, vec![ids[0], ids[1], ids[4], ids[2], ids[3]]);
+        assert_eq!(titles, vec!["Note 0", "Note 1", "Note 4", "Note 2", "Note 3"]);
+        
+        // Only the order changed; each id keeps its own note
+        assert_eq!(notes.get_note(ids[4]).1, "content 4");
+        assert_eq!(NoteOwner::get(&sdk, ids[2]), owner);
+        assert_eq!(notes.get_note(ids[2]).1, "content 2");
+    }
+    
+    #[test]
+    #[should_panic(expected = "Index out of range")]
+    fn test_reorder_note_out_of_range() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let first = notes.create_note("First".to_string(), "first".to_string());
+        notes.create_note("Second".to_string(), "second".to_string());
+        notes.reorder_note(first, U256::from(2));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note order must list each of your notes exactly once")]
+    fn test_set_notes_order_missing_id() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let first = notes.create_note("First".to_string(), "first".to_string());
+        notes.create_note("Second".to_string(), "second".to_string());
+        notes.set_notes_order(vec![first]);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note order must list each of your notes exactly once")]
+    fn test_set_notes_order_foreign_id() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut stranger_notes = as_caller(&sdk, stranger);
+        let foreign = stranger_notes.create_note("Theirs".to_string(), "theirs".to_string());
+        
+        let mut notes = as_caller(&sdk, owner);
+        let first = notes.create_note("First".to_string(), "first".to_string());
+        notes.set_notes_order(vec![foreign, first]);
+    }
+    
+    #[test]
+    fn test_notes_by_category() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let work = U256::from(1);
+        let home = U256::from(2);
+        let report = notes.create_note("Report".to_string(), "q3".to_string());
+        let chores = notes.create_note("Chores".to_string(), "dishes".to_string());
+        let meeting = notes.create_note("Meeting".to_string(), "notes".to_string());
+        let loose = notes.create_note("Loose".to_string(), "misc".to_string());
+        notes.set_note_category(report, work);
+        notes.set_note_category(chores, home);
+        notes.set_note_category(meeting, work);
+        
+        assert_eq!(notes.get_notes_by_category(work), (vec![report, meeting], vec!["Report".to_string(), "Meeting".to_string()]));
+        assert_eq!(notes.get_notes_by_category(home), (vec![chores], vec!["Chores".to_string()]));
+        assert_eq!(notes.get_notes_by_category(U256::from(0)), (vec![loose], vec!["Loose".to_string()]));
+        
+        // Purging clears the category of the purged note only
+        notes.purge_note(report);
+        assert_eq!(notes.get_notes_by_category(work), (vec![meeting], vec!["Meeting".to_string()]));
+        assert_eq!(notes.get_notes_by_category(home), (vec![chores], vec!["Chores".to_string()]));
+        assert_eq!(notes.get_notes_by_category(U256::from(0)), (vec![loose], vec!["Loose".to_string()]));
+        assert_eq!(NoteCategory::get(&sdk, report), U256::from(0));
+    }
+    
+    #[test]
+    fn test_note_categorized_event() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Report".to_string(), "q3".to_string());
+        take_event_logs(&sdk);
+        
+        let work = U256::from(7);
+        notes.set_note_category(note_id, work);
+        let logs = take_event_logs(&sdk);
+        assert_eq!(logs.len(), 1);
+        let (topics, data) = &logs[0];
+        assert_eq!(topics[0].as_slice(), EVENT_NOTE_CATEGORIZED);
+        assert_eq!(topics[1], address_topic(&owner));
+        assert_eq!(topics[2].as_slice(), note_id.to_be_bytes::<32>().as_slice());
+        assert_eq!(data.as_ref(), work.to_be_bytes::<32>().as_slice());
+        
+        // Same category again: nothing written, nothing emitted
+        notes.set_note_category(note_id, work);
+        assert!(take_event_logs(&sdk).is_empty());
+        assert_eq!(NoteCategory::get(&sdk, note_id), work);
+    }
+    
+    #[test]
+    fn test_note_links() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let index = notes.create_note("Index".to_string(), "start here".to_string());
+        let topic = notes.create_note("Topic".to_string(), "details".to_string());
+        let other = notes.create_note("Other".to_string(), "more".to_string());
+        
+        notes.link_notes(index, topic);
+        notes.link_notes(index, other);
+        // Linking the same pair again doesn't duplicate it
+        notes.link_notes(index, topic);
+        assert_eq!(notes.get_note_links(index), vec![topic, other]);
+        assert_eq!(notes.get_note_links(topic), Vec::<U256>::new());
+        
+        notes.unlink_notes(index, topic);
+        assert_eq!(notes.get_note_links(index), vec![other]);
+        
+        // A binned target drops out of the list, and comes back with the note
+        notes.delete_note(other);
+        assert_eq!(notes.get_note_links(index), Vec::<U256>::new());
+        notes.restore_note(other);
+        assert_eq!(notes.get_note_links(index), vec![other]);
+        
+        notes.purge_note(index);
+        assert_eq!(NoteLinkCount::get(&sdk, index), U256::from(0));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note does not exist")]
+    fn test_link_to_missing_note() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Index".to_string(), "start here".to_string());
+        notes.link_notes(note_id, note_id + U256::from(1));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note does not exist")]
+    fn test_link_to_foreign_note() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut stranger_notes = as_caller(&sdk, stranger);
+        let foreign = stranger_notes.create_note("Theirs".to_string(), "private".to_string());
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Index".to_string(), "start here".to_string());
+        notes.link_notes(note_id, foreign);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Too many tags")]
+    fn test_note_tags_cap() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Title".to_string(), "content".to_string());
+        let tags = (0..=MAX_TAGS_PER_NOTE).map(|i| format!("tag{}", i)).collect();
+        notes.set_note_tags(note_id, tags);
+    }
+    
+    #[test]
+    fn test_contract_statistics() {
+        let alice = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let bob = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut alice_notes = as_caller(&sdk, alice);
+        alice_notes.register_user(Bytes::from("alice key".as_bytes().to_vec()));
+        // Registering again, or auto-registering on create, doesn't count twice
+        alice_notes.register_user(Bytes::from("alice key 2".as_bytes().to_vec()));
+        let first = alice_notes.create_note("First".to_string(), "one".to_string());
+        alice_notes.create_note("Second".to_string(), "two".to_string());
+        
+        // Bob is registered on first use
+        let mut bob_notes = as_caller(&sdk, bob);
+        let bobs = bob_notes.create_note("Bob's".to_string(), "three".to_string());
+        assert_eq!(alice_notes.total_users(), U256::from(2));
+        assert_eq!(alice_notes.total_notes(), U256::from(3));
+        
+        alice_notes.delete_note(first);
+        assert_eq!(alice_notes.total_notes(), U256::from(2));
+        
+        // Purging a binned note doesn't count it twice; restoring brings it back
+        alice_notes.restore_note(first);
+        assert_eq!(alice_notes.total_notes(), U256::from(3));
+        alice_notes.delete_note(first);
+        alice_notes.purge_note(first);
+        assert_eq!(alice_notes.total_notes(), U256::from(2));
+        
+        // Transfers move a note between users without changing the total
+        bob_notes.transfer_note(bobs, alice);
+        assert_eq!(bob_notes.total_notes(), U256::from(2));
+        assert_eq!(bob_notes.total_users(), U256::from(2));
+    }
+    
+    #[test]
+    fn test_transfer_note() {
+        let sender = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let recipient = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, recipient);
+        notes.register_user(Bytes::from("recipient key".as_bytes().to_vec()));
+        
+        let mut notes = as_caller(&sdk, sender);
+        notes.register_user(Bytes::from("sender key".as_bytes().to_vec()));
+        let note_id = notes.create_note("Handover".to_string(), "handover content".to_string());
+        let new_id = notes.transfer_note(note_id, recipient);
+        assert_eq!(new_id, U256::from(1));
+        
+        // The sender no longer has or can read the note
+        assert_eq!(notes.get_note_count(), U256::from(0));
+        let (exists, _, _, _, _) = notes.try_get_note(note_id);
+        assert!(!exists);
+        
+        // The recipient owns it, re-encrypted under their own key
+        let notes = as_caller(&sdk, recipient);
+        assert_eq!(notes.get_note_count(), U256::from(1));
+        let (title, content, _, _) = notes.get_note(new_id);
+        assert_eq!(title, "Handover");
+        assert_eq!(content, "handover content");
+    }
+    
+    #[test]
+    #[should_panic(expected = "Transfer recipient has no encryption key")]
+    fn test_transfer_to_unregistered_recipient() {
+        let sender = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, sender);
+        let note_id = notes.create_note("Handover".to_string(), "handover content".to_string());
+        notes.transfer_note(note_id, stranger);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Transfer recipient has no encryption key")]
+    fn test_transfer_to_keyless_recipient() {
+        let sender = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        // Known to the contract through a note of their own, but no key set
+        let mut stranger_notes = as_caller(&sdk, stranger);
+        stranger_notes.create_note("Mine".to_string(), "content".to_string());
+        
+        let mut notes = as_caller(&sdk, sender);
+        let note_id = notes.create_note("Handover".to_string(), "handover content".to_string());
+        notes.transfer_note(note_id, stranger);
+    }
+    
+    #[test]
+    fn test_transfer_note_keeps_expiry() {
+        let sender = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let recipient = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        as_caller(&sdk, recipient).register_user(Bytes::from("recipient key".as_bytes().to_vec()));
+        
+        set_block_timestamp(&sdk, 100);
+        let mut notes = as_caller(&sdk, sender);
+        let note_id = notes.create_note_with_expiry("Ephemeral".to_string(), "gone soon".to_string(), U256::from(200));
+        let new_id = notes.transfer_note(note_id, recipient);
+        
+        // Still self-destructs on schedule in the recipient's hands
+        let recipient_notes = as_caller(&sdk, recipient);
+        assert_eq!(NoteExpiresAt::get(&sdk, new_id), U256::from(200));
+        assert!(recipient_notes.try_get_note(new_id).0);
+        set_block_timestamp(&sdk, 200);
+        assert!(recipient_notes.is_expired(new_id));
+        assert!(!recipient_notes.try_get_note(new_id).0);
+    }
+    
+    #[test]
+    fn test_note_ids_are_global() {
+        let alice = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let bob = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut alice_notes = as_caller(&sdk, alice);
+        let alice_id = alice_notes.create_note("Alice".to_string(), "alice content".to_string());
+        let mut bob_notes = as_caller(&sdk, bob);
+        let bob_id = bob_notes.create_note("Bob".to_string(), "bob content".to_string());
+        
+        // Each caller gets a distinct id and their own note back
+        assert_eq!(alice_id, U256::from(0));
+        assert_eq!(bob_id, U256::from(1));
+        assert_eq!(alice_notes.get_note(alice_id).1, "alice content");
+        assert_eq!(bob_notes.get_note(bob_id).1, "bob content");
+        assert_eq!(NoteOwner::get(&sdk, alice_id), alice);
+        assert_eq!(NoteOwner::get(&sdk, bob_id), bob);
+        
+        // Neither sees the other's note
+        let (exists, _, _, _, _) = alice_notes.try_get_note(bob_id);
+        assert!(!exists);
+        let (ids, _, _, _) = bob_notes.get_notes_list();
+        assert_eq!(ids, vec![bob_id]);
+    }
+    
+    #[test]
+    fn test_purge_leaves_other_users_notes() {
+        let alice = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let bob = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        // Both users hold a note at list position 0
+        let mut alice_notes = as_caller(&sdk, alice);
+        let alice_id = alice_notes.create_note("Shared title".to_string(), "alice content".to_string());
+        let mut bob_notes = as_caller(&sdk, bob);
+        let bob_id = bob_notes.create_note("Shared title".to_string(), "bob content".to_string());
+        
+        alice_notes.purge_note(alice_id);
+        assert_eq!(alice_notes.get_note_count(), U256::from(0));
+        
+        let (title, content, _, _) = bob_notes.get_note(bob_id);
+        assert_eq!(title, "Shared title");
+        assert_eq!(content, "bob content");
+        assert_eq!(bob_notes.find_note_by_title("Shared title".to_string()), (true, bob_id));
+    }
+    
+    #[test]
+    fn test_import_encrypted_note() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.register_user(Bytes::from("owner key".as_bytes().to_vec()));
+        let encrypted = notes.encrypt_note("migrated content".to_string());
+        
+        // The import is re-sealed under the note's own content key
+        let note_id = notes.import_encrypted_note("Migrated".to_string(), Bytes::from(encrypted[OWNER_PREFIX_LEN..].to_vec()));
+        assert_ne!(load_note_content(&sdk, &note_id), encrypted);
+        
+        let (title, content, _, _) = notes.get_note(note_id);
+        assert_eq!(title, "Migrated");
+        assert_eq!(content, "migrated content");
+        assert_eq!(notes.decrypt_note(encrypted), "migrated content");
+        assert!(notes.verify_note_integrity(note_id));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Imported content does not decrypt under your key")]
+    fn test_import_encrypted_note_wrong_key() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let encrypted = notes.encrypt_note("old key content".to_string());
+        notes.register_user(Bytes::from("new key".as_bytes().to_vec()));
+        notes.import_encrypted_note("Stale".to_string(), Bytes::from(encrypted[OWNER_PREFIX_LEN..].to_vec()));
+    }
+    
+    #[test]
+    fn test_note_expiry() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        set_block_timestamp(&sdk, 100);
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note_with_expiry("Ephemeral".to_string(), "gone soon".to_string(), U256::from(200));
+        let lasting = notes.create_note("Lasting".to_string(), "stays".to_string());
+        
+        // Readable right up to the expiry
+        set_block_timestamp(&sdk, 199);
+        assert!(!notes.is_expired(note_id));
+        assert_eq!(notes.get_note(note_id).1, "gone soon");
+        
+        // From the expiry on it reads as missing, without being deleted
+        set_block_timestamp(&sdk, 200);
+        assert!(notes.is_expired(note_id));
+        assert!(!notes.is_expired(lasting));
+        assert!(!notes.try_get_note(note_id).0);
+        assert!(!notes.note_exists(owner, note_id));
+        let (ids, _, _, _) = notes.get_notes_list();
+        assert_eq!(ids, vec![lasting]);
+        assert_eq!(notes.get_note_count(), U256::from(2));
+        
+        // Purging still frees it
+        notes.purge_note(note_id);
+        assert_eq!(notes.get_note_count(), U256::from(1));
+        assert_eq!(NoteExpiresAt::get(&sdk, note_id), U256::from(0));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note does not exist")]
+    fn test_get_expired_note_reverts() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
         
-        // Check if note exists
-        if let Some(mut note) = load_note(&self.sdk, &caller, &note_id) {
-            // Encrypt the content
-            let encrypted_content = self.encrypt_note(content);
-            
-            // Update the note
-            note.encrypted_content = encrypted_content;
-            note.title = title;
-            note.timestamp = U256::from(self.sdk.context().block_timestamp());
-            
-            // Save updated note
-            store_note(&mut self.sdk, &caller, &note_id, &note);
-            
-            // Create topics for indexed parameters
-            let caller_bytes = caller.to_vec();
-            let mut padded_caller = [0u8; 32];
-            if caller_bytes.len() >= 20 {
-                padded_caller[12..32].copy_from_slice(&caller_bytes[0..20]);
-            }
-            let caller_topic = fluentbase_sdk::B256::from(padded_caller);
-            
-            let note_id_bytes = note_id.to_be_bytes::<32>();
-            let note_id_topic = fluentbase_sdk::B256::from(note_id_bytes);
-            
-            // Emit event
-            emit_event(&mut self.sdk, EVENT_NOTE_UPDATED, Bytes::new(), &[caller_topic, note_id_topic]);
-        }
+        let sdk = TestingContext::default();
+        
+        set_block_timestamp(&sdk, 100);
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note_with_expiry("Ephemeral".to_string(), "gone soon".to_string(), U256::from(150));
+        set_block_timestamp(&sdk, 150);
+        notes.get_note(note_id);
     }
     
-    #[function_id("deleteNote(uint256)")]
-    fn delete_note(&mut self, note_id: U256) {
-        let caller = self.sdk.context().contract_caller();
+    #[test]
+    fn test_get_note_strict() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let reader = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
         
-        // Auto-register if not registered
-        if UserEncryptionKeys::get(&self.sdk, caller).is_empty() {
-            UserEncryptionKeys::set(&mut self.sdk, caller, Bytes::new());
-            
-            // Create caller address topic for indexed event parameter
-            let caller_bytes = caller.to_vec();
-            let mut padded_caller = [0u8; 32];
-            if caller_bytes.len() >= 20 {
-                padded_caller[12..32].copy_from_slice(&caller_bytes[0..20]);
-            }
-            let caller_topic = fluentbase_sdk::B256::from(padded_caller);
-            
-            emit_event(&mut self.sdk, EVENT_USER_REGISTERED, Bytes::new(), &[caller_topic]);
-        }
+        let sdk = TestingContext::default();
         
-        // Get notes count
-        let count = UserNotesCount::get(&self.sdk, caller);
+        set_block_timestamp(&sdk, 100);
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Strict".to_string(), "content".to_string());
+        as_caller(&sdk, reader).register_user(Bytes::from("reader key".as_bytes().to_vec()));
+        notes.share_note(note_id, reader);
         
-        // Check if note ID is valid and the note exists
-        if note_id >= count || load_note(&self.sdk, &caller, &note_id).is_none() {
-            return;
-        }
+        let expected = ("Strict".to_string(), "content".to_string(), U256::from(100), U256::from(100));
+        assert_eq!(notes.get_note_strict(note_id), expected);
+        assert_eq!(as_caller(&sdk, reader).get_note_strict(note_id), expected);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note does not exist")]
+    fn test_get_note_strict_missing() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
         
-        // Get last note ID
-        let last_id = count - U256::from(1);
+        let sdk = TestingContext::default();
         
-        if note_id != last_id {
-            // Move the last note to the deleted position
-            if let Some(last_note) = load_note(&self.sdk, &caller, &last_id) {
-                let mut moved_note = last_note.clone();
-                moved_note.id = note_id;
-                store_note(&mut self.sdk, &caller, &note_id, &moved_note);
-            }
-        }
+        as_caller(&sdk, owner).get_note_strict(U256::from(3));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Not authorized to read this note")]
+    fn test_get_note_strict_not_authorized() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
         
-        // Update count
-        UserNotesCount::set(&mut self.sdk, caller, count - U256::from(1));
+        let sdk = TestingContext::default();
         
-        // Create topics for indexed parameters
-        let caller_bytes = caller.to_vec();
-        let mut padded_caller = [0u8; 32];
-        if caller_bytes.len() >= 20 {
-            padded_caller[12..32].copy_from_slice(&caller_bytes[0..20]);
-        }
-        let caller_topic = fluentbase_sdk::B256::from(padded_caller);
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Private".to_string(), "content".to_string());
+        as_caller(&sdk, stranger).get_note_strict(note_id);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note is deleted")]
+    fn test_get_note_strict_deleted() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
         
-        let note_id_bytes = note_id.to_be_bytes::<32>();
-        let note_id_topic = fluentbase_sdk::B256::from(note_id_bytes);
+        let sdk = TestingContext::default();
         
-        // Emit event
-        emit_event(&mut self.sdk, EVENT_NOTE_DELETED, Bytes::new(), &[caller_topic, note_id_topic]);
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Binned".to_string(), "content".to_string());
+        notes.delete_note(note_id);
+        notes.get_note_strict(note_id);
     }
     
-    #[function_id("getNoteCount()")]
-    fn get_note_count(&self) -> U256 {
-        let caller = self.sdk.context().contract_caller();
-        UserNotesCount::get(&self.sdk, caller)
+    #[test]
+    #[should_panic(expected = "Note has expired")]
+    fn test_get_note_strict_expired() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        set_block_timestamp(&sdk, 100);
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note_with_expiry("Ephemeral".to_string(), "gone soon".to_string(), U256::from(150));
+        set_block_timestamp(&sdk, 150);
+        notes.get_note_strict(note_id);
     }
     
-    #[function_id("getNotesList()")]
-    fn get_notes_list(&self) -> (Vec<U256>, Vec<String>, Vec<U256>) {
-        let caller = self.sdk.context().contract_caller();
-        let notes = get_all_notes(&self.sdk, &caller);
+    #[test]
+    #[should_panic(expected = "Note content could not be decrypted")]
+    fn test_get_note_strict_corrupted() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
         
-        let mut ids = Vec::with_capacity(notes.len());
-        let mut titles = Vec::with_capacity(notes.len());
-        let mut timestamps = Vec::with_capacity(notes.len());
+        let sdk = TestingContext::default();
         
-        for note in &notes {
-            ids.push(note.id);
-            titles.push(note.title.clone());
-            timestamps.push(note.timestamp);
-        }
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Corrupted".to_string(), "content".to_string());
+        let mut raw_sdk = sdk.clone();
+        let mut ciphertext = load_note_content(&sdk, &note_id).to_vec();
+        let last = ciphertext.len() - 1;
+        ciphertext[last] ^= 0xff;
+        store_note_content(&mut raw_sdk, &note_id, &Bytes::from(ciphertext));
         
-        (ids, titles, timestamps)
+        notes.get_note_strict(note_id);
     }
     
-    #[function_id("updateEncryptionKey(bytes)")]
-    fn update_encryption_key(&mut self, new_key: Bytes) {
-        let caller = self.sdk.context().contract_caller();
-        UserEncryptionKeys::set(&mut self.sdk, caller, new_key);
+    #[test]
+    #[should_panic(expected = "Expiry must be in the future")]
+    fn test_note_expiry_in_past() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        set_block_timestamp(&sdk, 100);
+        let mut notes = as_caller(&sdk, owner);
+        notes.create_note_with_expiry("Late".to_string(), "late".to_string(), U256::from(100));
     }
     
-    #[function_id("encryptNote(string)")]
-    fn encrypt_note(&self, content: String) -> Bytes {
-        // Get caller address
-        let caller = self.sdk.context().contract_caller();
+    #[test]
+    fn test_set_note_expiry() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
         
-        // Get user's encryption key or use default if not set
-        let encryption_key = UserEncryptionKeys::get(&self.sdk, caller);
+        let sdk = TestingContext::default();
         
-        // Convert caller address to a usable form for encryption
-        let caller_bytes = caller.to_vec();
+        set_block_timestamp(&sdk, 100);
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Reminder".to_string(), "soon".to_string());
+        notes.set_note_expiry(note_id, U256::from(150));
         
-        // Convert encryption key to a usable form
-        let key_bytes = if encryption_key.is_empty() {
-            // Default key if user hasn't set one
-            caller_bytes.clone()
-        } else {
-            encryption_key.to_vec()
-        };
+        set_block_timestamp(&sdk, 149);
+        assert_eq!(notes.get_note(note_id).1, "soon");
+        
+        // Clearing the expiry keeps it around
+        notes.set_note_expiry(note_id, U256::from(0));
+        set_block_timestamp(&sdk, 150);
+        assert!(!notes.is_expired(note_id));
+        assert_eq!(notes.get_note(note_id).1, "soon");
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note is locked")]
+    fn test_set_expiry_on_locked_note_reverts() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        set_block_timestamp(&sdk, 100);
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Important".to_string(), "keep".to_string());
+        notes.lock_note(note_id);
+        notes.set_note_expiry(note_id, U256::from(150));
+    }
+    
+    #[test]
+    fn test_purge_expired() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
         
-        // Prepare result buffer with room for ownership data and content
-        let mut result = Vec::new();
+        let sdk = TestingContext::default();
         
-        // Add caller address to encrypted data for ownership verification
-        result.extend_from_slice(&caller_bytes);
+        set_block_timestamp(&sdk, 100);
+        let mut notes = as_caller(&sdk, owner);
+        let first = notes.create_note_with_expiry("First".to_string(), "temporary".to_string(), U256::from(200));
+        let lasting = notes.create_note("Lasting".to_string(), "stays".to_string());
+        let later = notes.create_note_with_expiry("Later".to_string(), "temporary".to_string(), U256::from(300));
+        let last = notes.create_note_with_expiry("Last".to_string(), "temporary".to_string(), U256::from(200));
         
-        // Simple XOR encryption (for demonstration - would use proper crypto in production)
-        for (i, byte) in content.as_bytes().iter().enumerate() {
-            // Use key byte as XOR mask, cycling through key bytes
-            let key_byte = key_bytes[i % key_bytes.len()];
-            result.push(byte ^ key_byte);
-        }
+        // Nothing has expired yet
+        assert_eq!(notes.purge_expired(U256::from(0), U256::from(10)), U256::from(0));
+        assert_eq!(notes.get_note_count(), U256::from(4));
+        
+        set_block_timestamp(&sdk, 200);
+        assert_eq!(notes.purge_expired(U256::from(0), U256::from(10)), U256::from(2));
+        assert_eq!(notes.get_note_count(), U256::from(2));
+        assert_eq!(NoteExpiresAt::get(&sdk, first), U256::from(0));
+        assert!(load_stored_note(&sdk, &owner, &last).is_none());
         
-        Bytes::from(result)
+        let (ids, _, _, _) = notes.get_notes_list();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&lasting));
+        assert!(ids.contains(&later));
     }
-
-    #[function_id("decryptNote(bytes)")]
-    fn decrypt_note(&self, encrypted_content: Bytes) -> String {
-        let caller = self.sdk.context().contract_caller();
-        let data = encrypted_content.to_vec();
+    
+    #[test]
+    fn test_note_content_type() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let recipient = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
         
-        // Validate data format and ownership
-        if data.len() < 20 {
-            return String::from("Error: Invalid data format");
-        }
+        let sdk = TestingContext::default();
         
-        // Extract the owner address from the encrypted data
-        let stored_address = &data[0..20];
-        let caller_bytes = caller.to_vec();
+        let mut notes = as_caller(&sdk, owner);
+        let plain = notes.create_note("Plain".to_string(), "text".to_string());
+        let markdown = notes.create_note_typed("Doc".to_string(), "# Heading".to_string(), CONTENT_TYPE_MARKDOWN);
+        assert_eq!(notes.get_note_content_type(plain), CONTENT_TYPE_PLAIN);
+        assert_eq!(notes.get_note_content_type(markdown), CONTENT_TYPE_MARKDOWN);
+        assert_eq!(notes.get_note_meta(markdown).3, CONTENT_TYPE_MARKDOWN);
+        assert_eq!(notes.get_note(markdown).1, "# Heading");
         
-        if stored_address != caller_bytes.as_slice() {
-            return String::from("Error: You don't have permission to decrypt this note");
-        }
+        // The type travels with a transfer and is cleared from the old id
+        as_caller(&sdk, recipient).register_user(Bytes::from("recipient key".as_bytes().to_vec()));
+        let moved = notes.transfer_note(markdown, recipient);
+        assert_eq!(NoteContentType::get(&sdk, markdown), U256::from(CONTENT_TYPE_PLAIN));
+        let recipient_notes = as_caller(&sdk, recipient);
+        assert_eq!(recipient_notes.get_note_content_type(moved), CONTENT_TYPE_MARKDOWN);
         
-        // Get user's encryption key
-        let encryption_key = UserEncryptionKeys::get(&self.sdk, caller);
-        let key_bytes = if encryption_key.is_empty() {
-            // Default key if user hasn't set one
-            caller_bytes
-        } else {
-            encryption_key.to_vec()
-        };
+        // And is cleared on purge
+        let json = notes.create_note_typed("Data".to_string(), "{}".to_string(), CONTENT_TYPE_JSON);
+        notes.purge_note(json);
+        assert_eq!(NoteContentType::get(&sdk, json), U256::from(CONTENT_TYPE_PLAIN));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Unknown content type")]
+    fn test_note_content_type_unknown() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.create_note_typed("Odd".to_string(), "?".to_string(), CONTENT_TYPE_JSON + 1);
+    }
+    
+    #[test]
+    fn test_set_note_type() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Readme".to_string(), "# Title".to_string());
+        assert_eq!(notes.get_note_meta(note_id).3, CONTENT_TYPE_PLAIN);
+        
+        notes.set_note_type(note_id, CONTENT_TYPE_MARKDOWN);
+        assert_eq!(notes.get_note_content_type(note_id), CONTENT_TYPE_MARKDOWN);
+        assert_eq!(notes.get_note_meta(note_id).3, CONTENT_TYPE_MARKDOWN);
+        
+        notes.set_note_type(note_id, CONTENT_TYPE_PLAIN);
+        assert_eq!(notes.get_note_meta(note_id).3, CONTENT_TYPE_PLAIN);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Unknown content type")]
+    fn test_set_note_type_unknown() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let note_id = notes.create_note("Readme".to_string(), "text".to_string());
+        notes.set_note_type(note_id, 7);
+    }
+    
+    #[test]
+    fn test_create_note_signed() {
+        let author = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let relayer = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        let key = test_signing_key();
+        
+        let sdk = TestingContext::default();
+        
+        // The relayer submits, the note belongs to the signer
+        let mut relayed = as_caller(&sdk, relayer);
+        let signature = sign_note(&key, author, "Signed", "gasless", U256::from(0));
+        let note_id = relayed.create_note_signed(author, "Signed".to_string(), "gasless".to_string(), U256::from(0), signature);
+        assert_eq!(NoteOwner::get(&sdk, note_id), author);
+        assert_eq!(relayed.get_note_count(), U256::from(0));
+        assert_eq!(relayed.get_signed_note_nonce(author), U256::from(1));
+        
+        let notes = as_caller(&sdk, author);
+        let (title, content, _, _) = notes.get_note(note_id);
+        assert_eq!(title, "Signed");
+        assert_eq!(content, "gasless");
+    }
+    
+    #[test]
+    fn test_create_note_for_owner() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let relayer = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.set_create_delegate(relayer, true);
+        assert!(notes.is_create_delegate(owner, relayer));
+        take_event_logs(&sdk);
+        
+        let mut relayed = as_caller(&sdk, relayer);
+        let note_id = relayed.create_note_for(owner, "Relayed".to_string(), "from a relayer".to_string());
+        assert_eq!(NoteOwner::get(&sdk, note_id), owner);
+        assert_eq!(relayed.get_note_count(), U256::from(0));
+        assert_eq!(notes.get_note(note_id).1, "from a relayer");
+        
+        // NoteCreated is indexed by the owner, not the delegate
+        let logs = take_event_logs(&sdk);
+        let (topics, _) = logs.iter().find(|(topics, _)| topics[0].as_slice() == EVENT_NOTE_CREATED).unwrap();
+        assert_eq!(topics[1], address_topic(&owner));
+        
+        notes.set_create_delegate(relayer, false);
+        assert!(!notes.is_create_delegate(owner, relayer));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Not an approved delegate")]
+    fn test_create_note_for_unapproved_caller() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        
+        let sdk = TestingContext::default();
+        
+        as_caller(&sdk, stranger).create_note_for(owner, "Spam".to_string(), "unwanted".to_string());
+    }
+    
+    #[test]
+    fn test_eip712_digest_vector() {
+        let author = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let contract = address!("5FbDB2315678afecb367f032d93F642f64180aa3");
+        
+        // Chain id and contract address both go into the domain, so set them on the context used
+        let sdk = TestingContext::default()
+            .with_block_context(BlockContextV1 {
+                chain_id: 1,
+                ..Default::default()
+            })
+            .with_contract_context(ContractContextV1 {
+                address: contract,
+                caller: author,
+                ..Default::default()
+            });
+        let notes = SecureNotes { sdk };
+        
+        // Computed off-chain with the same name, version, chain id and contract
+        assert_eq!(
+            notes.domain_separator(),
+            "3a3204fb03a367027f51faf61b00dcb8764e1077800f5e3391042380aaf031e1".parse::<B256>().unwrap()
+        );
+        assert_eq!(
+            notes.hash_create_note(author, "Hello".to_string(), "World".to_string(), U256::from(7)),
+            "4f7a4468e62c312f064ab3833fc5fef409e67b310714e26e29682634a558e7a7".parse::<B256>().unwrap()
+        );
+    }
+    
+    #[test]
+    #[should_panic(expected = "Invalid nonce")]
+    fn test_create_note_signed_replay() {
+        let author = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let relayer = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        let key = test_signing_key();
+        
+        let sdk = TestingContext::default();
+        
+        let mut relayed = as_caller(&sdk, relayer);
+        let signature = sign_note(&key, author, "Signed", "gasless", U256::from(0));
+        relayed.create_note_signed(author, "Signed".to_string(), "gasless".to_string(), U256::from(0), signature.clone());
+        relayed.create_note_signed(author, "Signed".to_string(), "gasless".to_string(), U256::from(0), signature);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Invalid signature")]
+    fn test_create_note_signed_wrong_author() {
+        let author = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let impersonated = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
+        let key = test_signing_key();
+        
+        let sdk = TestingContext::default();
+        
+        // A signature by one key can't create notes for another address
+        let mut relayed = as_caller(&sdk, author);
+        let signature = sign_note(&key, impersonated, "Forged", "forged", U256::from(0));
+        relayed.create_note_signed(impersonated, "Forged".to_string(), "forged".to_string(), U256::from(0), signature);
+    }
+    
+    #[test]
+    fn test_note_ids_never_overlap() {
+        let alice = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let bob = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut alice_notes = as_caller(&sdk, alice);
+        let mut bob_notes = as_caller(&sdk, bob);
+        let mut ids = Vec::new();
+        ids.push(alice_notes.create_note("A1".to_string(), "a1".to_string()));
+        ids.push(alice_notes.create_note("A2".to_string(), "a2".to_string()));
+        ids.extend(bob_notes.create_notes_batch(
+            vec![String::from("B1"), String::from("B2")],
+            vec![String::from("b1"), String::from("b2")],
+        ));
+        
+        // Purging doesn't hand an id out again
+        alice_notes.purge_note(ids[1]);
+        ids.push(alice_notes.create_note("A3".to_string(), "a3".to_string()));
+        
+        // Strictly increasing, so no two notes share an id
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(GlobalNoteCounter::get(&sdk), U256::from(ids.len()));
+    }
+    
+    #[test]
+    fn test_create_notes_batch_max_size() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
         
-        // Decrypt the content (reverse the encryption operation)
-        let mut decrypted = Vec::new();
-        for (i, byte) in data[20..].iter().enumerate() {
-            let key_byte = key_bytes[i % key_bytes.len()];
-            decrypted.push(byte ^ key_byte);
+        let mut notes = as_caller(&sdk, owner);
+        notes.create_note("Existing".to_string(), "existing".to_string());
+        
+        let titles: Vec<String> = (0..MAX_BATCH_SIZE).map(|i| format!("Imported {}", i)).collect();
+        let contents: Vec<String> = (0..MAX_BATCH_SIZE).map(|i| format!("content {}", i)).collect();
+        let ids = notes.create_notes_batch(titles, contents);
+        
+        // Ids are assigned in order after the existing note
+        assert_eq!(ids.len(), MAX_BATCH_SIZE);
+        assert_eq!(ids[0], U256::from(1));
+        assert_eq!(ids[MAX_BATCH_SIZE - 1], U256::from(MAX_BATCH_SIZE));
+        assert_eq!(notes.get_note_count(), U256::from(MAX_BATCH_SIZE + 1));
+        
+        let (title, content, _, _) = notes.get_note(ids[7]);
+        assert_eq!(title, "Imported 7");
+        assert_eq!(content, "content 7");
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note limit reached")]
+    fn test_note_limit_blocks_create() {
+        let admin = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let user = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, admin);
+        notes.deploy();
+        notes.set_user_note_limit(user, U256::from(3));
+        
+        // Filling up to the limit works, the next note reverts
+        let mut notes = as_caller(&sdk, user);
+        for i in 0..3 {
+            notes.create_note(format!("Note {}", i), "content".to_string());
         }
+        assert_eq!(notes.get_note_count(), U256::from(3));
+        notes.create_note("One too many".to_string(), "content".to_string());
+    }
+    
+    #[test]
+    #[should_panic(expected = "Note limit reached")]
+    fn test_default_note_limit() {
+        let user = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
         
-        // Convert decrypted bytes to string
-        match String::from_utf8(decrypted) {
-            Ok(s) => s,
-            Err(_) => String::from("Error: Decryption failed"),
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, user);
+        assert_eq!(notes.get_remaining_quota(), U256::from(MAX_NOTES_PER_USER));
+        
+        // Fill all but one slot in batches, then the last allowed note
+        let batches = (MAX_NOTES_PER_USER as usize - 1) / MAX_BATCH_SIZE;
+        for _ in 0..batches {
+            let titles = vec![String::from("Bulk"); MAX_BATCH_SIZE];
+            let contents = vec![String::from("content"); MAX_BATCH_SIZE];
+            notes.create_notes_batch(titles, contents);
         }
+        while notes.get_remaining_quota() > U256::from(1) {
+            notes.create_note("Fill".to_string(), "content".to_string());
+        }
+        let last = notes.create_note("Last".to_string(), "content".to_string());
+        assert_eq!(notes.get_remaining_quota(), U256::from(0));
+        
+        // Binning doesn't free a slot
+        notes.delete_note(last);
+        assert_eq!(notes.get_remaining_quota(), U256::from(0));
+        notes.create_note("One too many".to_string(), "content".to_string());
     }
     
-    // For compatibility with previous architecture where there were two contracts
-    #[function_id("getEncryptionContractAddress()")]
-    fn get_encryption_contract_address(&self) -> Address {
-        // Return this contract's address since all functionality is now here
-        self.sdk.context().contract_address()
+    #[test]
+    fn test_remaining_quota() {
+        let admin = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let user = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, admin);
+        notes.deploy();
+        notes.set_user_note_limit(user, U256::from(3));
+        
+        let mut user_notes = as_caller(&sdk, user);
+        let first = user_notes.create_note("First".to_string(), "content".to_string());
+        user_notes.create_note("Second".to_string(), "content".to_string());
+        assert_eq!(user_notes.get_remaining_quota(), U256::from(1));
+        
+        // Purging frees a slot
+        user_notes.purge_note(first);
+        assert_eq!(user_notes.get_remaining_quota(), U256::from(2));
+        
+        // Lowering the limit below the count reports no room rather than underflowing
+        notes.set_user_note_limit(user, U256::from(1));
+        assert_eq!(user_notes.get_remaining_quota(), U256::from(0));
     }
-}
-
-impl<SDK: SharedAPI> SecureNotes<SDK> {
-    // Deployment logic
-    fn deploy(&self) {
-        // Nothing special needed for deployment
+    
+    #[test]
+    #[should_panic(expected = "Note limit reached")]
+    fn test_note_limit_blocks_batch() {
+        let admin = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let user = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, admin);
+        notes.deploy();
+        notes.set_user_note_limit(user, U256::from(2));
+        
+        let mut notes = as_caller(&sdk, user);
+        notes.create_note("First".to_string(), "content".to_string());
+        notes.create_notes_batch(vec![String::from("A"), String::from("B")], vec![String::from("a"), String::from("b")]);
     }
-}
-
-// Define entry point for the contract
-basic_entrypoint!(SecureNotes);
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use fluentbase_sdk::{address, testing::TestingContext, ContractContextV1};
-
+    
     #[test]
-    fn test_note_operations() {
-        // Set up test context
-        let test_address = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
-        let test_title = "Test Note";
-        let test_content = "This is a test note content";
+    fn test_transfer_ownership() {
+        let admin = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let successor = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
         
-        let sdk = TestingContext::default().with_contract_context(ContractContextV1 {
-            caller: test_address,
-            ..Default::default()
-        });
+        let sdk = TestingContext::default();
         
-        let mut notes = SecureNotes { sdk: sdk.clone() };
+        let mut notes = as_caller(&sdk, admin);
+        notes.deploy();
+        assert_eq!(notes.owner(), admin);
         
-        // Test creating a note
-        let note_id = notes.create_note(test_title.to_string(), test_content.to_string());
+        notes.transfer_ownership(successor);
+        assert_eq!(ContractOwner::get(&sdk), successor);
         
-        // Test getting note count
-        let count = notes.get_note_count();
-        assert_eq!(count, U256::from(1));
+        // The new owner can use admin methods
+        let mut notes = as_caller(&sdk, successor);
+        notes.set_user_note_limit(admin, U256::from(5));
+        assert_eq!(UserNoteLimit::get(&sdk, admin), U256::from(5));
+    }
+    
+    #[test]
+    #[should_panic(expected = "Caller is not the contract owner")]
+    fn test_transfer_ownership_requires_owner() {
+        let admin = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let stranger = address!("3C44CdDdB6a900fa2b585dd299e03d12FA4293BC");
         
-        // Test getting note
-        let (title, content, _) = notes.get_note(note_id);
-        assert_eq!(title, test_title);
-        assert_eq!(content, test_content);
+        let sdk = TestingContext::default();
         
-        // Test updating note
-        let updated_title = "Updated Test Note";
-        let updated_content = "This is the updated content";
-        notes.update_note(note_id, updated_title.to_string(), updated_content.to_string());
+        let mut notes = as_caller(&sdk, admin);
+        notes.deploy();
         
-        // Verify update
-        let (title, content, _) = notes.get_note(note_id);
-        assert_eq!(title, updated_title);
-        assert_eq!(content, updated_content);
+        let mut notes = as_caller(&sdk, stranger);
+        notes.transfer_ownership(stranger);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Contract is paused")]
+    fn test_writes_revert_while_paused() {
+        let admin = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let user = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
         
-        // Test getting notes list
-        let (ids, titles, _) = notes.get_notes_list();
-        assert_eq!(ids.len(), 1);
-        assert_eq!(titles.len(), 1);
-        assert_eq!(titles[0], updated_title);
+        let sdk = TestingContext::default();
         
-        // Test deleting note
-        notes.delete_note(note_id);
+        let mut notes = as_caller(&sdk, admin);
+        notes.deploy();
         
-        // Verify deletion
-        let count = notes.get_note_count();
-        assert_eq!(count, U256::from(0));
+        let mut notes = as_caller(&sdk, user);
+        let note_id = notes.create_note("Before".to_string(), "written before the pause".to_string());
+        
+        let mut notes = as_caller(&sdk, admin);
+        notes.pause();
+        assert!(notes.paused());
+        
+        // Reads keep working while paused
+        let mut notes = as_caller(&sdk, user);
+        assert_eq!(notes.get_note_count(), U256::from(1));
+        let (ids, _, _, _) = notes.get_notes_list();
+        assert_eq!(ids, vec![note_id]);
+        let (_, content, _, _) = notes.get_note(note_id);
+        assert_eq!(content, "written before the pause");
+        
+        notes.update_note(note_id, "During".to_string(), "blocked".to_string());
+    }
+    
+    #[test]
+    fn test_writes_resume_after_unpause() {
+        let admin = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        let user = address!("70997970C51812dc3A010C7d01b50e0d17dc79C8");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, admin);
+        notes.deploy();
+        notes.pause();
+        notes.unpause();
+        assert!(!notes.paused());
+        
+        let mut notes = as_caller(&sdk, user);
+        let note_id = notes.create_note("After".to_string(), "content".to_string());
+        let (title, _, _, _) = notes.get_note(note_id);
+        assert_eq!(title, "After");
+    }
+    
+    #[test]
+    #[should_panic(expected = "Titles and contents length mismatch")]
+    fn test_create_notes_batch_length_mismatch() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        notes.create_notes_batch(vec!["One".to_string(), "Two".to_string()], vec!["one".to_string()]);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Batch exceeds maximum size")]
+    fn test_create_notes_batch_too_large() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        let mut notes = as_caller(&sdk, owner);
+        let titles = vec![String::from("Title"); MAX_BATCH_SIZE + 1];
+        let contents = vec![String::from("content"); MAX_BATCH_SIZE + 1];
+        notes.create_notes_batch(titles, contents);
+    }
+    
+    #[test]
+    fn test_create_notes_batch_matches_listing() {
+        let owner = address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        
+        let sdk = TestingContext::default();
+        
+        // An empty batch is a no-op
+        let mut notes = as_caller(&sdk, owner);
+        assert!(notes.create_notes_batch(Vec::new(), Vec::new()).is_empty());
+        assert_eq!(notes.get_note_count(), U256::from(0));
+        
+        // Returned ids line up with what the listing reports
+        let ids = notes.create_notes_batch(
+            vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+        );
+        let (listed_ids, titles, _, _) = notes.get_notes_list();
+        assert_eq!(ids, listed_ids);
+        assert_eq!(titles, vec!["A".to_string(), "B".to_string(), "C".to_string()]);
     }
-}
\ No newline at end of file
+}
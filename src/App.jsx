@@ -71,9 +71,9 @@ const ContractDiagnostics = ({ provider, account, onClose }) => {
             (viewErr.message || "Unknown error");
         }
         
-        // Try to encrypt some test data
+        // Try to encrypt some test data (simulated, encryptNote bumps the nonce counter on-chain)
         try {
-          const encryptedData = await contract.encryptNote("Test content", {
+          const encryptedData = await contract.callStatic.encryptNote("Test content", {
             gasLimit: 150000
           });
           
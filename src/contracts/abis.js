@@ -62,7 +62,12 @@ export const SecureNotesRustABI = [
       },
       {
         "internalType": "uint256",
-        "name": "timestamp",
+        "name": "created_at",
+        "type": "uint256"
+      },
+      {
+        "internalType": "uint256",
+        "name": "updated_at",
         "type": "uint256"
       }
     ],
@@ -88,7 +93,13 @@ export const SecureNotesRustABI = [
       }
     ],
     "name": "updateNote",
-    "outputs": [],
+    "outputs": [
+      {
+        "internalType": "uint256",
+        "name": "",
+        "type": "uint256"
+      }
+    ],
     "stateMutability": "nonpayable",
     "type": "function"
   },
@@ -101,7 +112,13 @@ export const SecureNotesRustABI = [
       }
     ],
     "name": "deleteNote",
-    "outputs": [],
+    "outputs": [
+      {
+        "internalType": "uint256",
+        "name": "",
+        "type": "uint256"
+      }
+    ],
     "stateMutability": "nonpayable",
     "type": "function"
   },
@@ -138,6 +155,11 @@ export const SecureNotesRustABI = [
         "internalType": "uint256[]",
         "name": "timestamps",
         "type": "uint256[]"
+      },
+      {
+        "internalType": "uint256[]",
+        "name": "created",
+        "type": "uint256[]"
       }
     ],
     "stateMutability": "view",
@@ -174,7 +196,7 @@ export const SecureNotesRustABI = [
         "type": "bytes"
       }
     ],
-    "stateMutability": "view",
+    "stateMutability": "nonpayable",
     "type": "function"
   },
   {
@@ -228,11 +250,29 @@ export const SecureNotesRustABI = [
         "name": "note_id",
         "type": "uint256"
       },
+      {
+        "indexed": true,
+        "internalType": "bytes32",
+        "name": "content_hash",
+        "type": "bytes32"
+      },
       {
         "indexed": false,
         "internalType": "string",
         "name": "title",
         "type": "string"
+      },
+      {
+        "indexed": false,
+        "internalType": "uint256",
+        "name": "content_length",
+        "type": "uint256"
+      },
+      {
+        "indexed": false,
+        "internalType": "uint256",
+        "name": "created_at",
+        "type": "uint256"
       }
     ],
     "name": "NoteCreated",
@@ -252,6 +292,36 @@ export const SecureNotesRustABI = [
         "internalType": "uint256",
         "name": "note_id",
         "type": "uint256"
+      },
+      {
+        "indexed": true,
+        "internalType": "bytes32",
+        "name": "content_hash",
+        "type": "bytes32"
+      },
+      {
+        "indexed": false,
+        "internalType": "string",
+        "name": "title",
+        "type": "string"
+      },
+      {
+        "indexed": false,
+        "internalType": "uint256",
+        "name": "content_length",
+        "type": "uint256"
+      },
+      {
+        "indexed": false,
+        "internalType": "uint256",
+        "name": "updated_at",
+        "type": "uint256"
+      },
+      {
+        "indexed": false,
+        "internalType": "string",
+        "name": "previous_title",
+        "type": "string"
       }
     ],
     "name": "NoteUpdated",
@@ -271,6 +341,12 @@ export const SecureNotesRustABI = [
         "internalType": "uint256",
         "name": "note_id",
         "type": "uint256"
+      },
+      {
+        "indexed": false,
+        "internalType": "uint256",
+        "name": "note_count",
+        "type": "uint256"
       }
     ],
     "name": "NoteDeleted",